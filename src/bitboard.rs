@@ -0,0 +1,235 @@
+use crate::board::{Color, Square};
+
+//One bit per square, a1 is bit 0, h1 is bit 7 and h8 is bit 63
+pub type Bitboard = u64;
+
+pub fn bit(square: Square) -> Bitboard {
+    1 << square.index()
+}
+
+//Iterates over the squares of the set bits, lowest first
+pub struct Squares(pub Bitboard);
+
+impl Iterator for Squares {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Square> {
+        if self.0 == 0 {
+            return None;
+        }
+        let square = Square::new(self.0.trailing_zeros() as usize);
+        self.0 &= self.0 - 1;
+        square
+    }
+}
+
+//Attack tables are built at compile time from these offsets
+const KNIGHT_OFFSETS: [(i32, i32); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_OFFSETS: [(i32, i32); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+//Ray directions. The first four point towards higher square indices
+const NORTH: usize = 0;
+const EAST: usize = 1;
+const NORTH_EAST: usize = 2;
+const NORTH_WEST: usize = 3;
+const SOUTH: usize = 4;
+const WEST: usize = 5;
+const SOUTH_WEST: usize = 6;
+const SOUTH_EAST: usize = 7;
+const DIRECTIONS: [(i32, i32); 8] = [
+    (0, 1),
+    (1, 0),
+    (1, 1),
+    (-1, 1),
+    (0, -1),
+    (-1, 0),
+    (-1, -1),
+    (1, -1),
+];
+
+const fn offset_table(offsets: &[(i32, i32); 8]) -> [Bitboard; 64] {
+    let mut table = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let x = (square % 8) as i32;
+        let y = (square / 8) as i32;
+        let mut i = 0;
+        while i < 8 {
+            let (tx, ty) = (x + offsets[i].0, y + offsets[i].1);
+            if tx >= 0 && tx < 8 && ty >= 0 && ty < 8 {
+                table[square] |= 1 << (ty * 8 + tx);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+const fn pawn_table() -> [[Bitboard; 64]; 2] {
+    let mut table = [[0; 64]; 2];
+    let mut square = 0;
+    while square < 64 {
+        let x = (square % 8) as i32;
+        let y = (square / 8) as i32;
+        let mut color = 0;
+        while color < 2 {
+            let ty = if color == 0 { y + 1 } else { y - 1 };
+            if ty >= 0 && ty < 8 {
+                if x > 0 {
+                    table[color][square] |= 1 << (ty * 8 + x - 1);
+                }
+                if x < 7 {
+                    table[color][square] |= 1 << (ty * 8 + x + 1);
+                }
+            }
+            color += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+const fn ray_table() -> [[Bitboard; 64]; 8] {
+    let mut table = [[0; 64]; 8];
+    let mut direction = 0;
+    while direction < 8 {
+        let (dx, dy) = DIRECTIONS[direction];
+        let mut square = 0;
+        while square < 64 {
+            let mut x = (square % 8) as i32 + dx;
+            let mut y = (square / 8) as i32 + dy;
+            while x >= 0 && x < 8 && y >= 0 && y < 8 {
+                table[direction][square] |= 1 << (y * 8 + x);
+                x += dx;
+                y += dy;
+            }
+            square += 1;
+        }
+        direction += 1;
+    }
+    table
+}
+
+const KNIGHT_ATTACKS: [Bitboard; 64] = offset_table(&KNIGHT_OFFSETS);
+const KING_ATTACKS: [Bitboard; 64] = offset_table(&KING_OFFSETS);
+const PAWN_ATTACKS: [[Bitboard; 64]; 2] = pawn_table();
+const RAYS: [[Bitboard; 64]; 8] = ray_table();
+
+pub fn knight_attacks(square: Square) -> Bitboard {
+    KNIGHT_ATTACKS[square.index()]
+}
+
+pub fn king_attacks(square: Square) -> Bitboard {
+    KING_ATTACKS[square.index()]
+}
+
+//The squares a pawn of the given colour standing on the square attacks
+pub fn pawn_attacks(square: Square, color: Color) -> Bitboard {
+    PAWN_ATTACKS[color as usize][square.index()]
+}
+
+//A ray stops at the first occupied square it meets, which is included so that
+//captures fall out of masking with the enemy pieces
+fn ray_attacks(square: Square, occupied: Bitboard, direction: usize) -> Bitboard {
+    let ray = RAYS[direction][square.index()];
+    let blockers = ray & occupied;
+    if blockers == 0 {
+        return ray;
+    }
+    let first = if direction < SOUTH {
+        blockers.trailing_zeros()
+    } else {
+        63 - blockers.leading_zeros()
+    };
+    ray ^ RAYS[direction][first as usize]
+}
+
+pub fn bishop_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    ray_attacks(square, occupied, NORTH_EAST)
+        | ray_attacks(square, occupied, NORTH_WEST)
+        | ray_attacks(square, occupied, SOUTH_EAST)
+        | ray_attacks(square, occupied, SOUTH_WEST)
+}
+
+pub fn rook_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    ray_attacks(square, occupied, NORTH)
+        | ray_attacks(square, occupied, EAST)
+        | ray_attacks(square, occupied, SOUTH)
+        | ray_attacks(square, occupied, WEST)
+}
+
+pub fn queen_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    bishop_attacks(square, occupied) | rook_attacks(square, occupied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaper_tables() {
+        //Knight in the corner and in the centre
+        assert_eq!(knight_attacks(Square::A1).count_ones(), 2);
+        assert_eq!(knight_attacks(Square::D4).count_ones(), 8);
+        assert_eq!(
+            king_attacks(Square::A1),
+            bit(Square::B1) | bit(Square::A2) | bit(Square::B2)
+        );
+        assert_eq!(king_attacks(Square::D4).count_ones(), 8);
+        //Pawns on the a and h files only attack one square
+        assert_eq!(pawn_attacks(Square::A2, Color::White), bit(Square::B3));
+        assert_eq!(pawn_attacks(Square::H2, Color::Black), bit(Square::G1));
+        assert_eq!(
+            pawn_attacks(Square::E2, Color::White),
+            bit(Square::D3) | bit(Square::F3)
+        );
+    }
+
+    #[test]
+    fn sliding_attacks_stop_at_blockers() {
+        assert_eq!(rook_attacks(Square::A1, 0).count_ones(), 14);
+        assert_eq!(bishop_attacks(Square::D4, 0).count_ones(), 13);
+        assert_eq!(queen_attacks(Square::D4, 0).count_ones(), 27);
+
+        //Rook on a1 with pieces on a3 and c1
+        let attacks = rook_attacks(Square::A1, bit(Square::A3) | bit(Square::C1));
+        assert_eq!(
+            attacks,
+            bit(Square::A2) | bit(Square::A3) | bit(Square::B1) | bit(Square::C1)
+        );
+
+        //Bishop on d4 with a blocker on f6 and one on b2
+        let attacks = bishop_attacks(Square::D4, bit(Square::F6) | bit(Square::B2));
+        assert_eq!(attacks & bit(Square::G7), 0);
+        assert_ne!(attacks & bit(Square::F6), 0);
+        assert_ne!(attacks & bit(Square::B2), 0);
+        assert_eq!(attacks & bit(Square::A1), 0);
+        assert_eq!(attacks.count_ones(), 10);
+    }
+
+    #[test]
+    fn squares_iterates_set_bits() {
+        let squares: Vec<Square> =
+            Squares(bit(Square::D1) | bit(Square::B3) | bit(Square::H8)).collect();
+        assert_eq!(squares, vec![Square::D1, Square::B3, Square::H8]);
+    }
+}
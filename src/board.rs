@@ -2,21 +2,50 @@ use std::cmp::PartialEq;
 use std::fmt::{Debug, Display};
 use std::marker::Copy;
 
+use crate::bitboard::{self, bit, Bitboard, Squares};
+use crate::piece_moves;
+use crate::render::RenderOptions;
+
+mod builder;
+pub mod fen;
+mod square;
+mod validate;
+mod zobrist;
+
+pub use builder::{BoardBuilder, PositionError};
+pub use square::{File, Rank, Square};
+pub use validate::PositionIssue;
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Color {
     White,
     Black,
 }
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub struct Coord {
-    pub x: isize,
-    pub y: isize,
-}
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Move {
-    pub from: Coord,
-    pub to: Coord,
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<PieceType>,
+}
+
+impl Move {
+    pub fn new(from: Square, to: Square) -> Move {
+        Move {
+            from,
+            to,
+            promotion: None,
+        }
+    }
+}
+
+impl Color {
+    pub fn opposite(&self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
 }
 
 impl Display for Color {
@@ -44,66 +73,209 @@ pub struct Piece {
 }
 
 impl Piece {
-    fn new(color: Color, piece_type: PieceType) -> Piece {
+    pub fn new(color: Color, piece_type: PieceType) -> Piece {
         Piece { piece_type, color }
     }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
-pub struct Square {
-    pub piece: Option<Piece>,
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+}
+
+impl CastlingRights {
+    pub fn none() -> CastlingRights {
+        CastlingRights {
+            white_kingside: false,
+            white_queenside: false,
+            black_kingside: false,
+            black_queenside: false,
+        }
+    }
+
+    pub fn kingside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white_kingside,
+            Color::Black => self.black_kingside,
+        }
+    }
+
+    pub fn queenside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white_queenside,
+            Color::Black => self.black_queenside,
+        }
+    }
+
+    //A king or rook leaving or being captured on its home square loses the matching rights
+    fn update_for_square(&mut self, square: Square) {
+        match square {
+            Square::E1 => {
+                self.white_kingside = false;
+                self.white_queenside = false;
+            }
+            Square::H1 => self.white_kingside = false,
+            Square::A1 => self.white_queenside = false,
+            Square::E8 => {
+                self.black_kingside = false;
+                self.black_queenside = false;
+            }
+            Square::H8 => self.black_kingside = false,
+            Square::A8 => self.black_queenside = false,
+            _ => (),
+        }
+    }
+}
+
+//Everything needed to take a move back that cannot be worked out from the move itself
+#[derive(Clone, Debug, PartialEq)]
+pub struct UndoInfo {
+    m: Move,
+    moved: Piece,
+    captured: Option<(Square, Piece)>,
+    castling_rights: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    hash: u64,
+}
+
+impl UndoInfo {
+    pub fn played_move(&self) -> Move {
+        self.m
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
-#[allow(dead_code)]
+pub enum IllegalMove {
+    EmptySquare(Square),
+    WrongColor(Square),
+    NotLegal(Move),
+}
+
+impl Display for IllegalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            IllegalMove::EmptySquare(c) => write!(f, "There is no piece on {}", c),
+            IllegalMove::WrongColor(c) => {
+                write!(f, "The piece on {} belongs to the other side", c)
+            }
+            IllegalMove::NotLegal(m) => write!(f, "{} is not a legal move", m),
+        }
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
+#[derive(Clone, Debug)]
 pub struct Board {
     current_turn: Color,
-    squares: [[Square; 8]; 8],
+    castling_rights: CastlingRights,
+    en_passant: Option<Square>,
+    //The squares array answers "what is on this square", the bitboards
+    //answer "where are the pieces of this kind" for move generation
+    squares: [Option<Piece>; 64],
+    pieces: [Bitboard; 6],
+    colors: [Bitboard; 2],
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    //Zobrist key of the position, kept up to date as pieces move
+    hash: u64,
+    //The key of every position reached since the board was set up, including
+    //the current one
+    history: Vec<u64>,
+}
+
+//Boards are equal when they hold the same position, however they got there,
+//so the history of earlier positions is left out
+impl PartialEq for Board {
+    fn eq(&self, other: &Board) -> bool {
+        self.current_turn == other.current_turn
+            && self.castling_rights == other.castling_rights
+            && self.en_passant == other.en_passant
+            && self.squares == other.squares
+            && self.pieces == other.pieces
+            && self.colors == other.colors
+            && self.halfmove_clock == other.halfmove_clock
+            && self.fullmove_number == other.fullmove_number
+            && self.hash == other.hash
+    }
 }
 
-#[allow(dead_code)]
 impl Board {
-    pub fn get_square(&self, x: isize, y: isize) -> &Square {
-        let x = x as usize;
-        let y = y as usize;
-        if x > 7 || y > 7 {
-            panic!("Invalid square: {}, {}", x, y);
+    fn set_square(&mut self, square: Square, piece: Option<Piece>) {
+        let b = bit(square);
+        if let Some(old) = self.get_piece(square) {
+            self.pieces[old.piece_type as usize] &= !b;
+            self.colors[old.color as usize] &= !b;
+            self.hash ^= zobrist::piece_key(old, square);
+        }
+        if let Some(new) = piece {
+            self.pieces[new.piece_type as usize] |= b;
+            self.colors[new.color as usize] |= b;
+            self.hash ^= zobrist::piece_key(new, square);
+        }
+        self.squares[square.index()] = piece;
+    }
+
+    fn empty() -> Board {
+        Board {
+            current_turn: Color::White,
+            castling_rights: CastlingRights::none(),
+            en_passant: None,
+            squares: [None; 64],
+            pieces: [0; 6],
+            colors: [0; 2],
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            hash: 0,
+            history: Vec::new(),
         }
-        &self.squares[x][y]
     }
 
-    fn set_square(&mut self, x: isize, y: isize, square: Square) {
-        self.squares[x as usize][y as usize] = square;
+    pub fn pieces(&self, piece_type: PieceType, color: Color) -> Bitboard {
+        self.pieces[piece_type as usize] & self.colors[color as usize]
+    }
+
+    pub fn occupied_by(&self, color: Color) -> Bitboard {
+        self.colors[color as usize]
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
     }
 
-    fn get_piece(&self, x: isize, y: isize) -> Option<Piece> {
-        self.get_square(x, y).piece
+    pub fn get_piece(&self, square: Square) -> Option<Piece> {
+        self.squares[square.index()]
     }
 
-    fn set_piece(&mut self, x: isize, y: isize, piece: Piece) {
-        let mut square = self.get_square(x, y).clone();
-        square.piece = Some(piece);
-        self.set_square(x, y, square);
+    //Places a piece without any checks, replacing whatever was there. These
+    //can leave the board in an impossible state, so they stay inside the
+    //crate and BoardBuilder is the way to edit positions from outside.
+    pub(crate) fn set_piece(&mut self, square: Square, piece: Piece) {
+        self.set_square(square, Some(piece));
     }
 
-    fn remove_piece(&mut self, x: isize, y: isize) {
-        let mut square = self.get_square(x, y).clone();
-        square.piece = None;
-        self.set_square(x, y, square);
+    pub(crate) fn remove_piece(&mut self, square: Square) {
+        self.set_square(square, None);
     }
 
-    pub fn move_piece(&mut self, x1: isize, y1: isize, x2: isize, y2: isize) {
-        let piece = self.get_piece(x1, y1);
-        self.remove_piece(x1, y1);
-        self.set_piece(x2, y2, piece.unwrap());
+    //Panics if there is no piece on `from`, so it stays inside the crate
+    pub(crate) fn move_piece(&mut self, from: Square, to: Square) {
+        let piece = self.get_piece(from);
+        self.remove_piece(from);
+        self.set_piece(to, piece.unwrap());
     }
 
     pub fn to_fen_string(&self) -> String {
         let mut fen = String::new();
         let mut empty = 0;
-        for y in (0..8).rev() {
-            for x in 0..8 {
-                match self.get_piece(x, y) {
+        for rank in Rank::ALL.into_iter().rev() {
+            for file in File::ALL {
+                match self.get_piece(Square::from_file_rank(file, rank)) {
                     Some(piece) => {
                         if empty > 0 {
                             fen.push_str(&empty.to_string());
@@ -163,7 +335,7 @@ impl Board {
                 fen.push_str(&empty.to_string());
                 empty = 0;
             }
-            if y > 0 {
+            if rank != Rank::First {
                 fen.push('/');
             }
         }
@@ -172,212 +344,348 @@ impl Board {
             Color::White => fen.push('w'),
             Color::Black => fen.push('b'),
         }
+        fen.push(' ');
+        let rights = self.castling_rights;
+        if rights == CastlingRights::none() {
+            fen.push('-');
+        }
+        if rights.white_kingside {
+            fen.push('K');
+        }
+        if rights.white_queenside {
+            fen.push('Q');
+        }
+        if rights.black_kingside {
+            fen.push('k');
+        }
+        if rights.black_queenside {
+            fen.push('q');
+        }
+        fen.push(' ');
+        match self.en_passant {
+            Some(target) => fen.push_str(&target.to_string()),
+            None => fen.push('-'),
+        }
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock, self.fullmove_number
+        ));
         fen
     }
 
-    pub fn board_from_fen_string(fen: String) -> Board {
-        let mut board = Board {
-            current_turn: Color::White,
-            squares: [[Square { piece: None }; 8]; 8],
-        };
-        let mut x = 0;
-        let mut y = 7;
-        let bp: Vec<&str> = fen.split(" ").collect();
-        let b = bp[0];
-        let p = bp[1];
-        board.current_turn = match p {
-            "w" => Color::White,
-            "b" => Color::Black,
-            _ => panic!("Invalid FEN string"),
-        };
-
-        for c in b.chars() {
-            if x > 8 || y > 8 {
-                panic!("Invalid FEN string");
-            }
-            match c {
-                'P' => {
-                    board.squares[x][y].piece = Some(Piece::new(Color::White, PieceType::Pawn));
-                    x += 1;
-                }
-                'N' => {
-                    board.squares[x][y].piece = Some(Piece::new(Color::White, PieceType::Knight));
-                    x += 1;
-                }
-                'B' => {
-                    board.squares[x][y].piece = Some(Piece::new(Color::White, PieceType::Bishop));
-                    x += 1;
-                }
-                'R' => {
-                    board.squares[x][y].piece = Some(Piece::new(Color::White, PieceType::Rook));
-                    x += 1;
-                }
-                'Q' => {
-                    board.squares[x][y].piece = Some(Piece::new(Color::White, PieceType::Queen));
-                    x += 1;
-                }
-                'K' => {
-                    board.squares[x][y].piece = Some(Piece::new(Color::White, PieceType::King));
-                    x += 1;
-                }
-                'p' => {
-                    board.squares[x][y].piece = Some(Piece::new(Color::Black, PieceType::Pawn));
-                    x += 1;
-                }
-                'n' => {
-                    board.squares[x][y].piece = Some(Piece::new(Color::Black, PieceType::Knight));
-                    x += 1;
-                }
-                'b' => {
-                    board.squares[x][y].piece = Some(Piece::new(Color::Black, PieceType::Bishop));
-                    x += 1;
-                }
-                'r' => {
-                    board.squares[x][y].piece = Some(Piece::new(Color::Black, PieceType::Rook));
-                    x += 1;
-                }
-                'q' => {
-                    board.squares[x][y].piece = Some(Piece::new(Color::Black, PieceType::Queen));
-                    x += 1;
-                }
-                'k' => {
-                    board.squares[x][y].piece = Some(Piece::new(Color::Black, PieceType::King));
-                    x += 1;
-                }
-                '/' => {
-                    x = 0;
-                    y -= 1;
-                }
-                '1' => x += 1,
-                '2' => x += 2,
-                '3' => x += 3,
-                '4' => x += 4,
-                '5' => x += 5,
-                '6' => x += 6,
-                '7' => x += 7,
-                '8' => x += 8,
-                _ => (),
-            }
+    //Panics on malformed input and allows partial positions, so it is only
+    //for known good FEN inside the crate; str::parse is the checked version
+    pub(crate) fn board_from_fen_string(fen: String) -> Board {
+        match fen::parse_fen(&fen) {
+            Ok(board) => board,
+            Err(e) => panic!("Invalid FEN string: {}", e),
         }
-        board
     }
 
     pub fn construct_board() -> Board {
-        Board::board_from_fen_string("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w".to_string())
+        Board::board_from_fen_string(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
+        )
     }
-}
 
-macro_rules! write_with_error {
-    ($f:expr, $fmt:expr) => {
-        match write!($f, $fmt) {
-            Ok(_) => (),
-            Err(e) => println!("Error: {}", e)
-        }
-    };
-    ($f:expr, $fmt:expr, $($arg:tt)*) => {
-        match write!($f, $fmt, $($arg)*) {
-            Ok(_) => (),
-            Err(e) => println!("Error: {}", e)
-        }
-    };
-}
+    pub fn current_turn(&self) -> Color {
+        self.current_turn
+    }
 
-macro_rules! writeln_with_error {
-    ($f:expr, $fmt:expr) => {
-        match writeln!($f, $fmt) {
-            Ok(_) => (),
-            Err(e) => println!("Error: {}", e)
-        }
-    };
-    ($f:expr, $fmt:expr, $($arg:tt)*) => {
-        match writeln!($f, $fmt, $($arg)*) {
-            Ok(_) => (),
-            Err(e) => println!("Error: {}", e)
-        }
-    };
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    //How many times the current position has occurred, counting this occurrence
+    pub fn repetition_count(&self) -> usize {
+        //Nothing before the last capture or pawn move can come around again
+        let reversible = (self.halfmove_clock as usize + 1).min(self.history.len());
+        self.history
+            .iter()
+            .rev()
+            .take(reversible)
+            .filter(|hash| **hash == self.hash)
+            .count()
+    }
+
+    //The Zobrist key of the position, equal for positions with the same pieces,
+    //side to move, castling rights and en passant capture
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.castling_rights
+    }
+
+    //The square a pawn just skipped over with a double push, if any
+    pub fn en_passant_target(&self) -> Option<Square> {
+        self.en_passant
+    }
 }
 
+//The plain diagram, see Board::render for the other styles
 impl Display for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        writeln_with_error!(f, "{} to move", self.current_turn);
-        //Print board starting from the 8th rank
-        for i in (0..8).rev() {
-            for j in 0..8 {
-                let Some(Piece {
-                    color: c,
-                    piece_type: p,
-                }) = self.get_square(j, i).piece else {
-                    write_with_error!(f, "-");
-                    continue;
-                };
-                let piece = match p {
-                    PieceType::Pawn => "P",
-                    PieceType::Knight => "N",
-                    PieceType::Bishop => "B",
-                    PieceType::Rook => "R",
-                    PieceType::Queen => "Q",
-                    PieceType::King => "K",
-                };
-                let colored_piece = match c {
-                    Color::White => piece.to_uppercase(),
-                    Color::Black => piece.to_lowercase(),
-                };
-                write_with_error!(f, "{}", colored_piece);
-            }
-            writeln_with_error!(f, "");
-        }
-        write!(f, "")
+        write!(f, "{}", self.render(&RenderOptions::default()))
     }
 }
 
 impl Board {
-    fn validate_piece_moves(&self, moves: Vec<Move>) -> Vec<Move> {
-        let mut validated_moves: Vec<Move> = Vec::new();
-        for m in moves {
-            validated_moves.push(m);
-
-            //Moves should not be to squares outside the board
-            if m.to.x > 7 || m.to.x < 0 || m.to.y > 7 || m.to.y < 0 {
-                validated_moves.pop();
-                continue;
+    pub fn get_available_moves_for_square(&self, square: Square) -> Vec<Move> {
+        let Some(piece) = self.get_piece(square) else { return Vec::new() };
+        let mut moves = Vec::new();
+        piece_moves::moves_for_piece(self, square, piece, &mut moves);
+        moves
+    }
+
+    //Every move for the side to move that does not leave its own king in check
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::with_capacity(64);
+        for from in Squares(self.occupied_by(self.current_turn)) {
+            let Some(piece) = self.get_piece(from) else { continue };
+            piece_moves::moves_for_piece(self, from, piece, &mut moves);
+        }
+        moves.retain(|m| {
+            let through_check = self.is_castling_move(*m) && self.castles_through_check(*m);
+            !through_check && !self.leaves_king_in_check(*m)
+        });
+        moves
+    }
+
+    pub fn is_in_check(&self, color: Color) -> bool {
+        let Some(king) = Squares(self.pieces(PieceType::King, color)).next() else { return false };
+        self.attackers_to(king, color.opposite(), self.occupied()) != 0
+    }
+
+    //Works out the occupancy after the move rather than playing it, then
+    //looks for anything attacking the king through the new occupancy
+    fn leaves_king_in_check(&self, m: Move) -> bool {
+        let Some(piece) = self.get_piece(m.from) else { return false };
+        let mut occupied = (self.occupied() & !bit(m.from)) | bit(m.to);
+        let mut captured = bit(m.to);
+        if piece.piece_type == PieceType::Pawn && Some(m.to) == self.en_passant {
+            let square = Square::from_file_rank(m.to.file(), m.from.rank());
+            captured |= bit(square);
+            occupied &= !bit(square);
+        }
+        if self.is_castling_move(m) {
+            let (rook_from, rook_to) = castling_rook_squares(m);
+            occupied = (occupied & !bit(rook_from)) | bit(rook_to);
+        }
+
+        let king = if piece.piece_type == PieceType::King {
+            m.to
+        } else {
+            let Some(king) = Squares(self.pieces(PieceType::King, piece.color)).next() else {
+                return false;
+            };
+            king
+        };
+        self.attackers_to(king, piece.color.opposite(), occupied) & !captured != 0
+    }
+
+    fn is_castling_move(&self, m: Move) -> bool {
+        match self.get_piece(m.from) {
+            Some(piece) => {
+                piece.piece_type == PieceType::King
+                    && m.from.file().index().abs_diff(m.to.file().index()) == 2
             }
+            None => false,
+        }
+    }
 
-            let Some(target_piece) = self.get_square(m.to.x, m.to.y).piece else { continue };
-            let Some(piece) = self.get_square(m.from.x, m.from.y).piece else { continue };
-            //Moves should not be to pieces of the same color
-            if piece.color == target_piece.color {
-                validated_moves.pop();
+    //The king may not castle out of check or across an attacked square
+    fn castles_through_check(&self, m: Move) -> bool {
+        let opponent = self.current_turn.opposite();
+        //The king starts and ends on the same rank, so the square it crosses
+        //is halfway between the two
+        let Some(crossed) = Square::new((m.from.index() + m.to.index()) / 2) else { return true };
+        self.is_square_attacked(m.from, opponent) || self.is_square_attacked(crossed, opponent)
+    }
+
+    //Checks the move against the legal move list before playing it
+    pub fn make_move(&mut self, m: Move) -> Result<UndoInfo, IllegalMove> {
+        let Some(piece) = self.get_piece(m.from) else {
+            return Err(IllegalMove::EmptySquare(m.from));
+        };
+        if piece.color != self.current_turn {
+            return Err(IllegalMove::WrongColor(m.from));
+        }
+        if !self.legal_moves().contains(&m) {
+            return Err(IllegalMove::NotLegal(m));
+        }
+        Ok(self.apply_move(m))
+    }
+
+    //Plays a move without checking that it is legal. Only for moves already
+    //known to be legal, as a move from an empty square panics; make_move is
+    //the checked version for everything else.
+    pub(crate) fn apply_move(&mut self, m: Move) -> UndoInfo {
+        let moved = self.get_piece(m.from).unwrap();
+        let moving_pawn = moved.piece_type == PieceType::Pawn;
+        let castling = self.is_castling_move(m);
+        let mut undo = UndoInfo {
+            m,
+            moved,
+            captured: None,
+            castling_rights: self.castling_rights,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: self.hash,
+        };
+        //Pieces keep their part of the key up to date, the rest is swapped over here
+        self.hash ^= zobrist::castling_key(self.castling_rights) ^ zobrist::en_passant_key(self);
+
+        if castling {
+            let (rook_from, rook_to) = castling_rook_squares(m);
+            self.move_piece(rook_from, rook_to);
+        }
+        if let Some(captured) = self.get_piece(m.to) {
+            undo.captured = Some((m.to, captured));
+        } else if moving_pawn && Some(m.to) == self.en_passant {
+            //The captured pawn sits beside the moving pawn, not on the target square
+            let square = Square::from_file_rank(m.to.file(), m.from.rank());
+            let pawn = Piece::new(self.current_turn.opposite(), PieceType::Pawn);
+            if self.get_piece(square) == Some(pawn) {
+                undo.captured = Some((square, pawn));
+                self.remove_piece(square);
             }
         }
-        validated_moves
-    }
-    #[allow(dead_code)]
-    pub fn get_available_moves_for_square(&self, x: isize, y: isize) -> Vec<Move> {
-        use crate::piece_moves;
-        let Some(piece) = self.get_square(x, y).piece else { return Vec::new() };
-        let moves = match piece.piece_type {
-            PieceType::Pawn => piece_moves::pawn_moves(*self, x, y, piece),
-            PieceType::Knight => piece_moves::knight_moves(x, y),
-            PieceType::Bishop => piece_moves::bishop_moves(*self, x, y),
-            PieceType::Rook => piece_moves::rook_moves(*self, x, y),
-            PieceType::Queen => piece_moves::queen_moves(*self, x, y),
-            PieceType::King => piece_moves::king_moves(x, y),
+        //The skipped square is halfway between the two ends of a double push
+        self.en_passant = if moving_pawn && m.from.rank().index().abs_diff(m.to.rank().index()) == 2
+        {
+            Square::new((m.from.index() + m.to.index()) / 2)
+        } else {
+            None
         };
-        self.validate_piece_moves(moves)
+        self.castling_rights.update_for_square(m.from);
+        self.castling_rights.update_for_square(m.to);
+        self.move_piece(m.from, m.to);
+        if let Some(piece_type) = m.promotion {
+            self.set_piece(m.to, Piece::new(self.current_turn, piece_type));
+        }
+        //FEN allows counters up to u32::MAX, so they stop there rather than overflow
+        if self.current_turn == Color::Black {
+            self.fullmove_number = self.fullmove_number.saturating_add(1);
+        }
+        self.current_turn = self.current_turn.opposite();
+
+        if moving_pawn || undo.captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+        }
+        self.hash ^= zobrist::side_key()
+            ^ zobrist::castling_key(self.castling_rights)
+            ^ zobrist::en_passant_key(self);
+        self.history.push(self.hash);
+        undo
+    }
+
+    //Takes back a move made with make_move or apply_move. Moves must be
+    //unmade in the reverse order they were made.
+    pub fn unmake_move(&mut self, undo: UndoInfo) {
+        let m = undo.m;
+        self.history.pop();
+        self.current_turn = self.current_turn.opposite();
+
+        self.remove_piece(m.to);
+        self.set_piece(m.from, undo.moved);
+        if let Some((square, piece)) = undo.captured {
+            self.set_piece(square, piece);
+        }
+        if undo.moved.piece_type == PieceType::King
+            && m.from.file().index().abs_diff(m.to.file().index()) == 2
+        {
+            let (rook_from, rook_to) = castling_rook_squares(m);
+            self.move_piece(rook_to, rook_from);
+        }
+
+        self.castling_rights = undo.castling_rights;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.hash = undo.hash;
+    }
+
+    //Whether any piece of the given colour attacks the square, whether or not
+    //the square is occupied and whether or not the attacker is pinned
+    pub fn is_square_attacked(&self, square: Square, by: Color) -> bool {
+        self.attackers_to(square, by, self.occupied()) != 0
+    }
+
+    pub fn attackers_of(&self, square: Square, by: Color) -> Vec<Square> {
+        Squares(self.attackers_to(square, by, self.occupied())).collect()
+    }
+
+    //Every square attacked by at least one piece of the given colour. Pawns
+    //attack diagonally whether or not there is anything there to take.
+    pub fn attack_map(&self, by: Color) -> Bitboard {
+        let occupied = self.occupied();
+        let mut attacked = 0;
+        for square in Squares(self.occupied_by(by)) {
+            let Some(piece) = self.get_piece(square) else { continue };
+            attacked |= match piece.piece_type {
+                PieceType::Pawn => bitboard::pawn_attacks(square, by),
+                PieceType::Knight => bitboard::knight_attacks(square),
+                PieceType::Bishop => bitboard::bishop_attacks(square, occupied),
+                PieceType::Rook => bitboard::rook_attacks(square, occupied),
+                PieceType::Queen => bitboard::queen_attacks(square, occupied),
+                PieceType::King => bitboard::king_attacks(square),
+            };
+        }
+        attacked
+    }
+
+    //Every piece of the given colour attacking the square. Each kind of attacker
+    //is found by looking outwards from the square as that kind of piece, which
+    //reaches exactly the squares such an attacker could stand on.
+    fn attackers_to(&self, square: Square, by: Color, occupied: Bitboard) -> Bitboard {
+        let diagonal = self.pieces(PieceType::Bishop, by) | self.pieces(PieceType::Queen, by);
+        let straight = self.pieces(PieceType::Rook, by) | self.pieces(PieceType::Queen, by);
+        (bitboard::pawn_attacks(square, by.opposite()) & self.pieces(PieceType::Pawn, by))
+            | (bitboard::knight_attacks(square) & self.pieces(PieceType::Knight, by))
+            | (bitboard::king_attacks(square) & self.pieces(PieceType::King, by))
+            | (bitboard::bishop_attacks(square, occupied) & diagonal)
+            | (bitboard::rook_attacks(square, occupied) & straight)
+    }
+}
+
+//Where the rook starts and ends for a castling move, given as the king's move
+fn castling_rook_squares(m: Move) -> (Square, Square) {
+    let rank = m.from.rank();
+    if m.to.file() > m.from.file() {
+        (
+            Square::from_file_rank(File::H, rank),
+            Square::from_file_rank(File::F, rank),
+        )
+    } else {
+        (
+            Square::from_file_rank(File::A, rank),
+            Square::from_file_rank(File::D, rank),
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::Square;
+
     #[test]
     fn moving_piece() {
         let mut board = super::Board::construct_board();
-        board.move_piece(0, 1, 0, 3);
+        board.move_piece(Square::A2, Square::A4);
         assert_eq!(
-            board.get_square(0, 3).piece.unwrap().piece_type,
+            board.get_piece(Square::A4).unwrap().piece_type,
             super::PieceType::Pawn
         );
-        assert_eq!(board.get_square(0, 1).piece, None);
+        assert_eq!(board.get_piece(Square::A2), None);
     }
 
     #[test]
@@ -386,55 +694,55 @@ mod tests {
             "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w".to_string(),
         );
         assert_eq!(
-            board.get_square(0, 7).piece.unwrap().piece_type,
+            board.get_piece(Square::A8).unwrap().piece_type,
             super::PieceType::Rook
         );
         assert_eq!(
-            board.get_square(0, 7).piece.unwrap().color,
+            board.get_piece(Square::A8).unwrap().color,
             super::Color::Black
         );
         //Check a white piece
         assert_eq!(
-            board.get_square(0, 0).piece.unwrap().piece_type,
+            board.get_piece(Square::A1).unwrap().piece_type,
             super::PieceType::Rook
         );
         assert_eq!(
-            board.get_square(0, 0).piece.unwrap().color,
+            board.get_piece(Square::A1).unwrap().color,
             super::Color::White
         );
         //Check and empty space
-        assert_eq!(board.get_square(3, 3).piece, None);
+        assert_eq!(board.get_piece(Square::D4), None);
     }
 
     #[test]
     fn pawn_moves() {
         //White home pawn
         let board = super::Board::construct_board();
-        let moves = board.get_available_moves_for_square(0, 1);
+        let moves = board.get_available_moves_for_square(Square::A2);
         assert_eq!(moves.len(), 2);
-        assert_eq!(moves[0].to, super::Coord { x: 0, y: 2 });
-        assert_eq!(moves[1].to, super::Coord { x: 0, y: 3 });
+        assert_eq!(moves[0].to, Square::A3);
+        assert_eq!(moves[1].to, Square::A4);
 
         //Black home pawn
         let board = super::Board::construct_board();
-        let moves = board.get_available_moves_for_square(0, 6);
+        let moves = board.get_available_moves_for_square(Square::A7);
         assert_eq!(moves.len(), 2);
-        assert_eq!(moves[0].to, super::Coord { x: 0, y: 5 });
-        assert_eq!(moves[1].to, super::Coord { x: 0, y: 4 });
+        assert_eq!(moves[0].to, Square::A6);
+        assert_eq!(moves[1].to, Square::A5);
 
         //Test moves for a non home pawn
         let board = super::Board::board_from_fen_string("8/8/8/8/3p4/8/8/8 b".to_string());
-        let moves = board.get_available_moves_for_square(3, 3);
+        let moves = board.get_available_moves_for_square(Square::D4);
         assert_eq!(moves.len(), 1);
 
         //Test moves for a pawn that can't move
         let board = super::Board::board_from_fen_string("8/8/8/8/3p4/3p4/8/8 b".to_string());
-        let moves = board.get_available_moves_for_square(3, 3);
+        let moves = board.get_available_moves_for_square(Square::D4);
         assert_eq!(moves.len(), 0, "Blocked pawn should have no moves");
 
         //Capture moves
         let board = super::Board::board_from_fen_string("8/8/8/8/3p4/4P3/8/8 b".to_string());
-        let moves = board.get_available_moves_for_square(3, 3);
+        let moves = board.get_available_moves_for_square(Square::D4);
         assert_eq!(
             moves.len(),
             2,
@@ -445,59 +753,462 @@ mod tests {
     #[test]
     fn knight_moves() {
         let board = super::Board::construct_board();
-        let moves = board.get_available_moves_for_square(1, 0);
+        let moves = board.get_available_moves_for_square(Square::B1);
         assert_eq!(moves.len(), 2);
         //TODO not very robust
-        assert_eq!(moves[1].to, super::Coord { x: 2, y: 2 });
-        assert_eq!(moves[0].to, super::Coord { x: 0, y: 2 });
+        assert_eq!(moves[1].to, Square::C3);
+        assert_eq!(moves[0].to, Square::A3);
 
         let board = super::Board::board_from_fen_string("8/8/8/8/3n4/8/8/8 b".to_string());
-        let moves = board.get_available_moves_for_square(3, 3);
+        let moves = board.get_available_moves_for_square(Square::D4);
         assert_eq!(moves.len(), 8);
     }
 
     #[test]
     fn bishop_moves() {
         let board = super::Board::board_from_fen_string("8/8/8/8/3b4/8/8/8 b".to_string());
-        let moves = board.get_available_moves_for_square(3, 3);
+        let moves = board.get_available_moves_for_square(Square::D4);
         assert_eq!(moves.len(), 13);
 
         //Blocked by friendly piece
         let board = super::Board::board_from_fen_string("8/8/8/8/3b4/4b3/8/8 b".to_string());
-        let moves = board.get_available_moves_for_square(3, 3);
+        let moves = board.get_available_moves_for_square(Square::D4);
         assert_eq!(moves.len(), 10);
 
         //Blocked by taking piece
         let board = super::Board::board_from_fen_string("8/8/8/8/3b4/4B3/8/8 b".to_string());
-        let moves = board.get_available_moves_for_square(3, 3);
+        let moves = board.get_available_moves_for_square(Square::D4);
         assert_eq!(moves.len(), 11);
     }
 
     #[test]
     fn rook_moves() {
         let board = super::Board::board_from_fen_string("8/8/8/8/3r4/8/8/8 b".to_string());
-        let moves = board.get_available_moves_for_square(3, 3);
+        let moves = board.get_available_moves_for_square(Square::D4);
         assert_eq!(moves.len(), 14)
     }
 
     #[test]
     fn queen_moves() {
         let board = super::Board::board_from_fen_string("8/8/8/8/3q4/8/8/8 b".to_string());
-        let moves = board.get_available_moves_for_square(3, 3);
+        let moves = board.get_available_moves_for_square(Square::D4);
         assert_eq!(moves.len(), 27)
     }
 
     #[test]
     fn king_moves() {
         let board = super::Board::board_from_fen_string("8/8/8/8/3k4/8/8/8 b".to_string());
-        let moves = board.get_available_moves_for_square(3, 3);
+        let moves = board.get_available_moves_for_square(Square::D4);
         assert_eq!(moves.len(), 8)
     }
 
+    #[test]
+    fn pawn_on_edge_file() {
+        let board = super::Board::board_from_fen_string("8/8/8/8/8/8/7P/8 w".to_string());
+        let moves = board.get_available_moves_for_square(Square::H2);
+        assert_eq!(moves.len(), 2);
+
+        //Pawns cannot capture straight ahead
+        let board = super::Board::board_from_fen_string("8/8/8/8/8/7p/7P/8 w".to_string());
+        let moves = board.get_available_moves_for_square(Square::H2);
+        assert_eq!(moves.len(), 0);
+    }
+
+    #[test]
+    fn legal_moves_from_start() {
+        let board = super::Board::construct_board();
+        assert_eq!(board.legal_moves().len(), 20);
+    }
+
+    #[test]
+    fn pinned_piece_cannot_move() {
+        let board = super::Board::board_from_fen_string("4r3/8/8/8/8/8/4B3/4K3 w".to_string());
+        let moves = board.legal_moves();
+        assert!(moves.iter().all(|m| m.from == Square::E1));
+        assert_eq!(moves.len(), 4);
+
+        //Moving the knight would discover an attack on the king
+        let board = super::Board::board_from_fen_string("k7/8/8/8/8/8/8/KN5r w".to_string());
+        assert_eq!(board.legal_moves().len(), 2);
+    }
+
+    #[test]
+    fn check_must_be_answered() {
+        let board = super::Board::board_from_fen_string("4r2k/8/8/8/8/8/R7/4K3 w".to_string());
+        assert!(board.is_in_check(super::Color::White));
+        let moves = board.legal_moves();
+        assert_eq!(moves.len(), 5);
+        assert!(moves.contains(&super::Move::new(Square::A2, Square::E2)));
+    }
+
+    #[test]
+    fn king_cannot_walk_into_check() {
+        let board = super::Board::board_from_fen_string("8/8/8/8/8/8/r7/4K3 w".to_string());
+        let moves = board.legal_moves();
+        assert_eq!(moves.len(), 2);
+    }
+
+    #[test]
+    fn castling() {
+        use super::Move;
+        let kingside = Move::new(Square::E1, Square::G1);
+        let queenside = Move::new(Square::E1, Square::C1);
+
+        let board =
+            super::Board::board_from_fen_string("r3k2r/8/8/8/8/8/8/R3K2R w KQkq".to_string());
+        let moves = board.legal_moves();
+        assert!(moves.contains(&kingside));
+        assert!(moves.contains(&queenside));
+
+        //No rights, no castling
+        let board = super::Board::board_from_fen_string("r3k2r/8/8/8/8/8/8/R3K2R w kq".to_string());
+        let moves = board.legal_moves();
+        assert!(!moves.contains(&kingside));
+        assert!(!moves.contains(&queenside));
+
+        //Pieces in the way
+        let board =
+            super::Board::board_from_fen_string("r3k2r/8/8/8/8/8/8/RN2K1NR w KQkq".to_string());
+        let moves = board.legal_moves();
+        assert!(!moves.contains(&kingside));
+        assert!(!moves.contains(&queenside));
+    }
+
+    #[test]
+    fn castling_through_or_out_of_check() {
+        use super::Move;
+        let kingside = Move::new(Square::E1, Square::G1);
+        let queenside = Move::new(Square::E1, Square::C1);
+
+        //Rook on f8 covers f1
+        let board = super::Board::board_from_fen_string("5r1k/8/8/8/8/8/8/R3K2R w KQ".to_string());
+        let moves = board.legal_moves();
+        assert!(!moves.contains(&kingside));
+        assert!(moves.contains(&queenside));
+
+        //In check from e8
+        let board = super::Board::board_from_fen_string("4r2k/8/8/8/8/8/8/R3K2R w KQ".to_string());
+        let moves = board.legal_moves();
+        assert!(!moves.contains(&kingside));
+        assert!(!moves.contains(&queenside));
+
+        //b1 being attacked does not stop queenside castling
+        let board = super::Board::board_from_fen_string("1r5k/8/8/8/8/8/8/R3K2R w KQ".to_string());
+        assert!(board.legal_moves().contains(&queenside));
+    }
+
+    #[test]
+    fn castling_moves_rook_and_updates_rights() {
+        use super::{Move, PieceType};
+        let mut board =
+            super::Board::board_from_fen_string("r3k2r/8/8/8/8/8/8/R3K2R w KQkq".to_string());
+        board.apply_move(Move::new(Square::E1, Square::G1));
+        assert_eq!(
+            board.get_piece(Square::F1).unwrap().piece_type,
+            PieceType::Rook
+        );
+        assert_eq!(board.get_piece(Square::H1), None);
+        assert_eq!(board.to_fen_string(), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+
+        //Moving a rook loses that side only
+        board.apply_move(Move::new(Square::A8, Square::B8));
+        assert_eq!(board.to_fen_string(), "1r2k2r/8/8/8/8/8/8/R4RK1 w k - 2 2");
+
+        //Capturing a rook on its home square removes the opponent's right
+        let mut board =
+            super::Board::board_from_fen_string("r3k2r/8/8/8/8/8/8/R3K2R w KQkq".to_string());
+        board.apply_move(Move::new(Square::H1, Square::H8));
+        assert_eq!(board.to_fen_string(), "r3k2R/8/8/8/8/8/8/R3K3 b Qq - 0 1");
+    }
+
+    #[test]
+    fn castling_rights_round_trip_through_fen() {
+        for fen in [
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 3 12",
+            "r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1",
+        ] {
+            let board = super::Board::board_from_fen_string(fen.to_string());
+            assert_eq!(board.to_fen_string(), fen);
+        }
+    }
+
+    #[test]
+    fn en_passant() {
+        use super::Move;
+        let mut board =
+            super::Board::board_from_fen_string("4k3/8/8/3p4/8/8/4P3/4K3 w - -".to_string());
+        board.apply_move(Move::new(Square::E2, Square::E4));
+        assert_eq!(board.en_passant_target(), Some(Square::E3));
+        assert_eq!(board.to_fen_string(), "4k3/8/8/3p4/4P3/8/8/4K3 b - e3 0 1");
+
+        //Black's pawn is not beside the pushed pawn yet
+        board.apply_move(Move::new(Square::D5, Square::D4));
+        assert_eq!(board.en_passant_target(), None);
+
+        let mut board =
+            super::Board::board_from_fen_string("4k3/8/8/8/3p4/8/4P3/4K3 w - -".to_string());
+        board.apply_move(Move::new(Square::E2, Square::E4));
+        let capture = Move::new(Square::D4, Square::E3);
+        assert!(board.legal_moves().contains(&capture));
+        board.apply_move(capture);
+        assert_eq!(board.get_piece(Square::E4), None);
+        assert_eq!(board.to_fen_string(), "4k3/8/8/8/8/4p3/8/4K3 w - - 0 2");
+    }
+
+    #[test]
+    fn en_passant_exposing_king_is_illegal() {
+        use super::Move;
+        //Both pawns leave the fifth rank, exposing the king to the rook
+        let board = super::Board::board_from_fen_string("8/8/8/K2pP2r/8/8/8/7k w - d6".to_string());
+        assert!(!board
+            .legal_moves()
+            .contains(&Move::new(Square::E5, Square::D6)));
+    }
+
+    #[test]
+    fn en_passant_needs_an_enemy_pawn() {
+        use super::Move;
+        let capture = Move::new(Square::E5, Square::D6);
+        //A piece on the target square
+        let board =
+            super::Board::board_from_fen_string("4k3/8/3N4/3pP3/8/8/8/4K3 w - d6 0 1".to_string());
+        assert!(!board.legal_moves().contains(&capture));
+        //A piece of the moving side beside the pawn
+        let mut board =
+            super::Board::board_from_fen_string("4k3/8/8/3NP3/8/8/8/4K3 w - d6 0 1".to_string());
+        assert!(!board.legal_moves().contains(&capture));
+        board.apply_move(capture);
+        assert_eq!(board.to_fen_string(), "4k3/8/3P4/3N4/8/8/8/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn counters_stop_at_their_limit() {
+        use super::Move;
+        let fen = "4k3/8/8/8/8/8/8/4K3 b - - 4294967295 4294967295";
+        let mut board = super::Board::board_from_fen_string(fen.to_string());
+        let undo = board.apply_move(Move::new(Square::E8, Square::D8));
+        assert_eq!(
+            board.to_fen_string(),
+            "3k4/8/8/8/8/8/8/4K3 w - - 4294967295 4294967295"
+        );
+        board.unmake_move(undo);
+        assert_eq!(board.to_fen_string(), fen);
+    }
+
+    #[test]
+    fn en_passant_round_trips_through_fen() {
+        let fen = "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3";
+        let board = super::Board::board_from_fen_string(fen.to_string());
+        assert_eq!(board.en_passant_target(), Some(Square::D6));
+        assert_eq!(board.to_fen_string(), fen);
+    }
+
+    #[test]
+    fn promotion() {
+        use super::{Move, Piece, PieceType};
+        let board = super::Board::board_from_fen_string("1n2k3/P7/8/8/8/8/8/4K3 w - -".to_string());
+        let moves = board.get_available_moves_for_square(Square::A7);
+        assert_eq!(
+            moves.len(),
+            8,
+            "Both the push and the capture promote to four pieces"
+        );
+        assert!(moves.iter().all(|m| m.promotion.is_some()));
+
+        let mut board = board;
+        let promotion = Move {
+            from: Square::A7,
+            to: Square::B8,
+            promotion: Some(PieceType::Knight),
+        };
+        assert!(board.legal_moves().contains(&promotion));
+        board.apply_move(promotion);
+        assert_eq!(
+            board.get_piece(Square::B8),
+            Some(Piece::new(super::Color::White, PieceType::Knight))
+        );
+        assert_eq!(board.get_piece(Square::A7), None);
+
+        //Black promotes on the first rank
+        let board = super::Board::board_from_fen_string("4k3/8/8/8/8/8/7p/K7 b - -".to_string());
+        let moves = board.get_available_moves_for_square(Square::H2);
+        assert_eq!(moves.len(), 4);
+    }
+
+    #[test]
+    fn make_move_rejects_illegal_moves() {
+        use super::{IllegalMove, Move};
+        let mut board = super::Board::construct_board();
+        let empty = Move::new(Square::E4, Square::E5);
+        assert_eq!(
+            board.make_move(empty),
+            Err(IllegalMove::EmptySquare(empty.from))
+        );
+        let black = Move::new(Square::E7, Square::E5);
+        assert_eq!(
+            board.make_move(black),
+            Err(IllegalMove::WrongColor(black.from))
+        );
+        let too_far = Move::new(Square::E2, Square::E5);
+        assert_eq!(
+            board.make_move(too_far),
+            Err(IllegalMove::NotLegal(too_far))
+        );
+        assert_eq!(board, super::Board::construct_board());
+    }
+
+    #[test]
+    fn make_move_switches_turn_and_clocks() {
+        use super::{Color, Move};
+        let mut board = super::Board::construct_board();
+        board.make_move(Move::new(Square::G1, Square::F3)).unwrap();
+        assert_eq!(board.current_turn(), Color::Black);
+        assert_eq!(board.halfmove_clock(), 1);
+        assert_eq!(board.fullmove_number(), 1);
+        board.make_move(Move::new(Square::E7, Square::E5)).unwrap();
+        assert_eq!(board.current_turn(), Color::White);
+        assert_eq!(board.halfmove_clock(), 0);
+        assert_eq!(board.fullmove_number(), 2);
+    }
+
+    #[test]
+    fn unmake_move_restores_position() {
+        use super::{Move, PieceType};
+        let cases = [
+            //Quiet move
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                Move::new(Square::G1, Square::F3),
+            ),
+            //Capture of a rook on its home square
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 5 20",
+                Move::new(Square::A1, Square::A8),
+            ),
+            //Castling
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 5 20",
+                Move::new(Square::E8, Square::C8),
+            ),
+            //En passant
+            (
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2",
+                Move::new(Square::E5, Square::D6),
+            ),
+            //Capturing promotion
+            (
+                "1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+                super::Move {
+                    from: Square::A7,
+                    to: Square::B8,
+                    promotion: Some(PieceType::Queen),
+                },
+            ),
+        ];
+        for (fen, m) in cases {
+            let original: super::Board = fen.parse().unwrap();
+            let mut board = original.clone();
+            let undo = board.make_move(m).unwrap();
+            assert_ne!(board, original);
+            board.unmake_move(undo);
+            assert_eq!(board, original, "{}", fen);
+            assert_eq!(board.to_fen_string(), fen);
+            assert_eq!(board.repetition_count(), 1);
+        }
+    }
+
+    #[test]
+    fn unmake_every_legal_move() {
+        let original: super::Board =
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+                .parse()
+                .unwrap();
+        let mut board = original.clone();
+        for m in original.legal_moves() {
+            let undo = board.make_move(m).unwrap();
+            for reply in board.legal_moves() {
+                let reply_undo = board.make_move(reply).unwrap();
+                board.unmake_move(reply_undo);
+            }
+            board.unmake_move(undo);
+            assert_eq!(board, original);
+            assert_eq!(board.repetition_count(), 1);
+        }
+    }
+
+    #[test]
+    fn equality_ignores_history() {
+        let mut board: super::Board = "4k3/8/8/8/8/8/8/4K3 w - - 0 1".parse().unwrap();
+        board.make_move("e1d1".parse().unwrap()).unwrap();
+        let parsed: super::Board = "4k3/8/8/8/8/8/8/3K4 b - - 1 1".parse().unwrap();
+        assert_eq!(board, parsed);
+
+        //The history is still there for repetitions
+        for m in ["e8d8", "d1e1", "d8e8", "e1d1"] {
+            board.make_move(m.parse().unwrap()).unwrap();
+        }
+        assert_eq!(board.repetition_count(), 2);
+        assert_eq!(parsed.repetition_count(), 1);
+    }
+
     #[test]
     fn empty_square_has_no_moves() {
         let board = super::Board::construct_board();
-        let moves = board.get_available_moves_for_square(4, 4);
+        let moves = board.get_available_moves_for_square(Square::E5);
         assert_eq!(moves.len(), 0);
     }
+
+    #[test]
+    fn square_attacks() {
+        use super::Color;
+        let board: super::Board = "4k3/8/8/3p4/8/2N5/8/R3K2B w - - 0 1".parse().unwrap();
+        //The black pawn reaches e4, as do the knight and the bishop on h1
+        assert!(board.is_square_attacked(Square::E4, Color::Black));
+        assert!(board.is_square_attacked(Square::E4, Color::White));
+        assert_eq!(
+            board.attackers_of(Square::E4, Color::Black),
+            vec![Square::D5]
+        );
+        assert_eq!(
+            board.attackers_of(Square::E4, Color::White),
+            vec![Square::H1, Square::C3]
+        );
+        //So is the pawn itself, an occupied square being attacked just the same
+        assert_eq!(
+            board.attackers_of(Square::D5, Color::White),
+            vec![Square::H1, Square::C3]
+        );
+        assert!(!board.is_square_attacked(Square::D5, Color::Black));
+        //Sliders stop at the first piece in the way
+        assert!(!board.is_square_attacked(Square::H8, Color::White));
+        assert!(board.attackers_of(Square::H8, Color::White).is_empty());
+    }
+
+    #[test]
+    fn attack_maps_agree_with_square_queries() {
+        use super::Color;
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        ] {
+            let board: super::Board = fen.parse().unwrap();
+            for color in [Color::White, Color::Black] {
+                let map = board.attack_map(color);
+                for square in Square::all() {
+                    assert_eq!(
+                        map >> square.index() & 1 == 1,
+                        board.is_square_attacked(square, color),
+                        "{} {}",
+                        color,
+                        square
+                    );
+                }
+            }
+        }
+        //Each side attacks its own third rank, pawns included, and nothing beyond
+        let board = super::Board::construct_board();
+        assert_eq!(board.attack_map(Color::White), 0x0000_0000_00FF_FF7E);
+        assert_eq!(board.attack_map(Color::Black), 0x7EFF_FF00_0000_0000);
+    }
 }
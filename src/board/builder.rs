@@ -0,0 +1,276 @@
+use std::fmt::Display;
+
+use super::{zobrist, Board, CastlingRights, Color, Piece, PositionIssue, Square};
+
+//Everything wrong with a position that failed to build
+#[derive(Clone, Debug, PartialEq)]
+pub struct PositionError {
+    pub issues: Vec<PositionIssue>,
+}
+
+impl Display for PositionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, issue) in self.issues.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+//Sets up a position piece by piece. Nothing is checked until build, so the
+//position may pass through impossible states while it is being edited.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoardBuilder {
+    squares: [Option<Piece>; 64],
+    side_to_move: Color,
+    castling: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+}
+
+impl BoardBuilder {
+    //An empty board with White to move and no castling rights
+    pub fn new() -> BoardBuilder {
+        BoardBuilder {
+            squares: [None; 64],
+            side_to_move: Color::White,
+            castling: CastlingRights::none(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+        }
+    }
+
+    //Starts from an existing position, to edit it
+    pub fn from_board(board: &Board) -> BoardBuilder {
+        BoardBuilder {
+            squares: board.squares,
+            side_to_move: board.current_turn,
+            castling: board.castling_rights,
+            en_passant: board.en_passant,
+            halfmove_clock: board.halfmove_clock,
+            fullmove_number: board.fullmove_number,
+        }
+    }
+
+    pub fn piece(&self, square: Square) -> Option<Piece> {
+        self.squares[square.index()]
+    }
+
+    //Replaces whatever is on the square
+    pub fn place(&mut self, square: Square, piece: Piece) -> &mut BoardBuilder {
+        self.squares[square.index()] = Some(piece);
+        self
+    }
+
+    pub fn clear(&mut self, square: Square) -> &mut BoardBuilder {
+        self.squares[square.index()] = None;
+        self
+    }
+
+    pub fn side_to_move(&mut self, color: Color) -> &mut BoardBuilder {
+        self.side_to_move = color;
+        self
+    }
+
+    pub fn castling(&mut self, rights: CastlingRights) -> &mut BoardBuilder {
+        self.castling = rights;
+        self
+    }
+
+    pub fn en_passant(&mut self, target: Option<Square>) -> &mut BoardBuilder {
+        self.en_passant = target;
+        self
+    }
+
+    pub fn halfmove_clock(&mut self, halfmove_clock: u32) -> &mut BoardBuilder {
+        self.halfmove_clock = halfmove_clock;
+        self
+    }
+
+    //Move numbers start at 1, so 0 is taken as 1
+    pub fn fullmove_number(&mut self, fullmove_number: u32) -> &mut BoardBuilder {
+        self.fullmove_number = fullmove_number.max(1);
+        self
+    }
+
+    //Fails with every issue Board::validate finds
+    pub fn build(&self) -> Result<Board, PositionError> {
+        let mut board = Board::empty();
+        for square in Square::all() {
+            if let Some(piece) = self.piece(square) {
+                board.set_piece(square, piece);
+            }
+        }
+        board.current_turn = self.side_to_move;
+        board.castling_rights = self.castling;
+        board.en_passant = self.en_passant;
+        board.halfmove_clock = self.halfmove_clock;
+        board.fullmove_number = self.fullmove_number;
+
+        let issues = board.validate();
+        if !issues.is_empty() {
+            return Err(PositionError { issues });
+        }
+        board.hash = zobrist::key(&board);
+        board.history.push(board.hash);
+        Ok(board)
+    }
+}
+
+impl Default for BoardBuilder {
+    fn default() -> BoardBuilder {
+        BoardBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoardBuilder, PositionError};
+    use crate::board::{Board, CastlingRights, Color, Piece, PieceType, PositionIssue, Square};
+
+    fn error(issues: &[PositionIssue]) -> Result<Board, PositionError> {
+        Err(PositionError {
+            issues: issues.to_vec(),
+        })
+    }
+
+    fn kings() -> BoardBuilder {
+        let mut builder = BoardBuilder::new();
+        builder
+            .place(Square::E1, Piece::new(Color::White, PieceType::King))
+            .place(Square::E8, Piece::new(Color::Black, PieceType::King));
+        builder
+    }
+
+    #[test]
+    fn builds_positions() {
+        let board = kings()
+            .place(Square::E4, Piece::new(Color::White, PieceType::Pawn))
+            .place(Square::D4, Piece::new(Color::Black, PieceType::Pawn))
+            .side_to_move(Color::Black)
+            .en_passant(Some(Square::E3))
+            .halfmove_clock(0)
+            .fullmove_number(12)
+            .build()
+            .unwrap();
+        assert_eq!(board.to_fen_string(), "4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 12");
+        //The key has to match a board set up the usual way
+        assert_eq!(board, board.to_fen_string().parse::<Board>().unwrap());
+        assert_eq!(board.legal_moves().len(), 7);
+    }
+
+    #[test]
+    fn edits_existing_positions() {
+        let mut builder = BoardBuilder::from_board(&Board::construct_board());
+        assert_eq!(builder.build(), Ok(Board::construct_board()));
+        builder
+            .clear(Square::B1)
+            .clear(Square::G1)
+            .clear(Square::D1)
+            .castling(CastlingRights {
+                white_kingside: true,
+                white_queenside: false,
+                black_kingside: true,
+                black_queenside: true,
+            });
+        assert_eq!(builder.piece(Square::B1), None);
+        assert_eq!(
+            builder.build().unwrap().to_fen_string(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1B1KB1R w Kkq - 0 1"
+        );
+    }
+
+    #[test]
+    fn rejects_impossible_positions() {
+        assert_eq!(
+            BoardBuilder::new().build(),
+            error(&[
+                PositionIssue::WrongKingCount {
+                    color: Color::White,
+                    count: 0
+                },
+                PositionIssue::WrongKingCount {
+                    color: Color::Black,
+                    count: 0
+                },
+            ])
+        );
+        assert_eq!(
+            kings()
+                .place(Square::A8, Piece::new(Color::Black, PieceType::King))
+                .build(),
+            error(&[PositionIssue::WrongKingCount {
+                color: Color::Black,
+                count: 2
+            }])
+        );
+        assert_eq!(
+            kings()
+                .place(Square::C1, Piece::new(Color::Black, PieceType::Pawn))
+                .build(),
+            error(&[PositionIssue::PawnOnBackRank(Square::C1)])
+        );
+        //Black is in check with White to move
+        assert_eq!(
+            kings()
+                .place(Square::E2, Piece::new(Color::White, PieceType::Rook))
+                .build(),
+            error(&[PositionIssue::OpponentInCheck(Color::Black)])
+        );
+        //Being in check on your own move is fine
+        assert!(kings()
+            .place(Square::E2, Piece::new(Color::White, PieceType::Rook))
+            .side_to_move(Color::Black)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn checks_castling_and_en_passant() {
+        let rights = CastlingRights {
+            white_kingside: true,
+            ..CastlingRights::none()
+        };
+        let result = kings().castling(rights).build();
+        assert_eq!(
+            result,
+            error(&[PositionIssue::ImpossibleCastling {
+                color: Color::White,
+                kingside: true
+            }])
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "White can't castle kingside without the king and rook at home"
+        );
+        assert!(kings()
+            .place(Square::H1, Piece::new(Color::White, PieceType::Rook))
+            .castling(rights)
+            .build()
+            .is_ok());
+
+        //White to move needs a black pawn that just went from d7 to d5
+        let mut builder = kings();
+        builder
+            .place(Square::D5, Piece::new(Color::Black, PieceType::Pawn))
+            .en_passant(Some(Square::D6));
+        assert!(builder.build().is_ok());
+        builder.en_passant(Some(Square::D3));
+        assert_eq!(
+            builder.build(),
+            error(&[PositionIssue::ImpossibleEnPassant(Square::D3)])
+        );
+        builder.en_passant(Some(Square::E6));
+        assert_eq!(
+            builder.build(),
+            error(&[PositionIssue::ImpossibleEnPassant(Square::E6)])
+        );
+    }
+}
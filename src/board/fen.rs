@@ -0,0 +1,343 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::{Board, CastlingRights, Color, File, Piece, PieceType, Rank, Square};
+
+//Ranks are numbered 1-8 as they are written on the board, not by their index
+#[derive(Clone, Debug, PartialEq)]
+pub enum FenError {
+    Empty,
+    MissingField(&'static str),
+    TooManyFields(usize),
+    WrongRankCount(usize),
+    RankTooLong { rank: u8 },
+    RankTooShort { rank: u8, squares: usize },
+    InvalidPiece { rank: u8, character: char },
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+    WrongKingCount { color: Color, count: usize },
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FenError::Empty => write!(f, "FEN string is empty"),
+            FenError::MissingField(field) => write!(f, "missing {} field", field),
+            FenError::TooManyFields(count) => {
+                write!(f, "expected 6 fields but found {}", count)
+            }
+            FenError::WrongRankCount(count) => {
+                write!(f, "expected 8 ranks but found {}", count)
+            }
+            FenError::RankTooLong { rank } => write!(f, "rank {} has more than 8 squares", rank),
+            FenError::RankTooShort { rank, squares } => {
+                write!(f, "rank {} only describes {} squares", rank, squares)
+            }
+            FenError::InvalidPiece { rank, character } => {
+                write!(f, "unknown piece '{}' on rank {}", character, rank)
+            }
+            FenError::InvalidSideToMove(field) => {
+                write!(f, "side to move must be 'w' or 'b', found '{}'", field)
+            }
+            FenError::InvalidCastling(field) => write!(f, "invalid castling rights '{}'", field),
+            FenError::InvalidEnPassant(field) => {
+                write!(f, "invalid en passant square '{}'", field)
+            }
+            FenError::InvalidHalfmoveClock(field) => {
+                write!(f, "invalid halfmove clock '{}'", field)
+            }
+            FenError::InvalidFullmoveNumber(field) => {
+                write!(f, "invalid fullmove number '{}'", field)
+            }
+            FenError::WrongKingCount { color, count } => {
+                write!(f, "{} must have exactly one king but has {}", color, count)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+impl FromStr for Board {
+    type Err = FenError;
+
+    fn from_str(fen: &str) -> Result<Board, FenError> {
+        let field_count = fen.split_whitespace().count();
+        if field_count > 6 {
+            return Err(FenError::TooManyFields(field_count));
+        }
+        let board = parse_fen(fen)?;
+        let missing = [
+            "side to move",
+            "castling",
+            "en passant",
+            "halfmove clock",
+            "fullmove number",
+        ];
+        if field_count < 6 {
+            return Err(FenError::MissingField(missing[field_count - 1]));
+        }
+
+        for color in [Color::White, Color::Black] {
+            let count = board.pieces(PieceType::King, color).count_ones() as usize;
+            if count != 1 {
+                return Err(FenError::WrongKingCount { color, count });
+            }
+        }
+        //The target has to be behind a pawn that could just have made a double push
+        if let Some(target) = board.en_passant {
+            if !super::validate::en_passant_possible(&board, target) {
+                return Err(FenError::InvalidEnPassant(target.to_string()));
+            }
+        }
+        Ok(board)
+    }
+}
+
+//Parses whichever fields are present, defaulting the rest. Used directly by
+//board_from_fen_string, which predates the full format and allows partial positions.
+pub(super) fn parse_fen(fen: &str) -> Result<Board, FenError> {
+    let mut fields = fen.split_whitespace();
+    let placement = fields.next().ok_or(FenError::Empty)?;
+    let mut board = Board::empty();
+
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::WrongRankCount(ranks.len()));
+    }
+    for (text, rank) in ranks.iter().zip(Rank::ALL.into_iter().rev()) {
+        let rank_number = rank.index() as u8 + 1;
+        let mut x = 0;
+        for c in text.chars() {
+            if let Some(empty) = c.to_digit(10).filter(|d| (1..=8).contains(d)) {
+                x += empty as usize;
+            } else {
+                let piece = piece_from_char(c).ok_or(FenError::InvalidPiece {
+                    rank: rank_number,
+                    character: c,
+                })?;
+                if let Some(file) = File::new(x) {
+                    board.set_piece(Square::from_file_rank(file, rank), piece);
+                }
+                x += 1;
+            }
+            if x > 8 {
+                return Err(FenError::RankTooLong { rank: rank_number });
+            }
+        }
+        if x < 8 {
+            return Err(FenError::RankTooShort {
+                rank: rank_number,
+                squares: x,
+            });
+        }
+    }
+
+    let Some(side) = fields.next() else { return Err(FenError::MissingField("side to move")) };
+    board.current_turn = match side {
+        "w" => Color::White,
+        "b" => Color::Black,
+        _ => return Err(FenError::InvalidSideToMove(side.to_string())),
+    };
+
+    //Older positions may leave out everything after the side to move
+    if let Some(castling) = fields.next() {
+        board.castling_rights = parse_castling(castling)?;
+    }
+    if let Some(en_passant) = fields.next() {
+        board.en_passant = match en_passant {
+            "-" => None,
+            name => match name.parse::<Square>().ok() {
+                //The skipped square is always on the third or sixth rank
+                Some(target) if matches!(target.rank(), Rank::Third | Rank::Sixth) => Some(target),
+                _ => return Err(FenError::InvalidEnPassant(name.to_string())),
+            },
+        };
+    }
+    if let Some(halfmove_clock) = fields.next() {
+        board.halfmove_clock = halfmove_clock
+            .parse()
+            .map_err(|_| FenError::InvalidHalfmoveClock(halfmove_clock.to_string()))?;
+    }
+    if let Some(fullmove_number) = fields.next() {
+        board.fullmove_number = match fullmove_number.parse() {
+            Ok(n) if n > 0 => n,
+            _ => return Err(FenError::InvalidFullmoveNumber(fullmove_number.to_string())),
+        };
+    }
+
+    board.hash = super::zobrist::key(&board);
+    board.history.push(board.hash);
+    Ok(board)
+}
+
+fn parse_castling(field: &str) -> Result<CastlingRights, FenError> {
+    let mut rights = CastlingRights::none();
+    if field == "-" {
+        return Ok(rights);
+    }
+    for c in field.chars() {
+        let right = match c {
+            'K' => &mut rights.white_kingside,
+            'Q' => &mut rights.white_queenside,
+            'k' => &mut rights.black_kingside,
+            'q' => &mut rights.black_queenside,
+            _ => return Err(FenError::InvalidCastling(field.to_string())),
+        };
+        //Each right may only be listed once
+        if *right {
+            return Err(FenError::InvalidCastling(field.to_string()));
+        }
+        *right = true;
+    }
+    Ok(rights)
+}
+
+fn piece_from_char(c: char) -> Option<Piece> {
+    let piece_type = match c.to_ascii_lowercase() {
+        'p' => PieceType::Pawn,
+        'n' => PieceType::Knight,
+        'b' => PieceType::Bishop,
+        'r' => PieceType::Rook,
+        'q' => PieceType::Queen,
+        'k' => PieceType::King,
+        _ => return None,
+    };
+    let color = if c.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    Some(Piece::new(color, piece_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FenError;
+    use crate::board::{Board, Color, Square};
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test]
+    fn parses_all_fields() {
+        let board: Board = "r3k2r/8/8/3pP3/8/8/8/R3K2R w Kq d6 7 31".parse().unwrap();
+        assert_eq!(board.current_turn(), Color::White);
+        assert!(board.castling_rights().white_kingside);
+        assert!(!board.castling_rights().white_queenside);
+        assert!(board.castling_rights().black_queenside);
+        assert_eq!(board.en_passant_target(), Some(Square::D6));
+        assert_eq!(board.halfmove_clock(), 7);
+        assert_eq!(board.fullmove_number(), 31);
+        assert_eq!(
+            board.to_fen_string(),
+            "r3k2r/8/8/3pP3/8/8/8/R3K2R w Kq d6 7 31"
+        );
+
+        let board: Board = START.parse().unwrap();
+        assert_eq!(board, Board::construct_board());
+    }
+
+    #[test]
+    fn placement_errors() {
+        let cases = [
+            ("", FenError::Empty),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::WrongRankCount(7),
+            ),
+            (
+                "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::InvalidPiece {
+                    rank: 6,
+                    character: '9',
+                },
+            ),
+            (
+                "rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::RankTooLong { rank: 7 },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P2/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1",
+                FenError::RankTooShort {
+                    rank: 4,
+                    squares: 7,
+                },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBXR w KQkq - 0 1",
+                FenError::InvalidPiece {
+                    rank: 1,
+                    character: 'X',
+                },
+            ),
+        ];
+        for (fen, error) in cases {
+            assert_eq!(fen.parse::<Board>(), Err(error), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn field_errors() {
+        let placement = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR";
+        let cases = [
+            ("", FenError::MissingField("side to move")),
+            (" w KQkq -", FenError::MissingField("halfmove clock")),
+            (
+                " x KQkq - 0 1",
+                FenError::InvalidSideToMove("x".to_string()),
+            ),
+            (
+                " w KQxq - 0 1",
+                FenError::InvalidCastling("KQxq".to_string()),
+            ),
+            (" w KKq - 0 1", FenError::InvalidCastling("KKq".to_string())),
+            (
+                " w KQkq e4 0 1",
+                FenError::InvalidEnPassant("e4".to_string()),
+            ),
+            (
+                " w KQkq i3 0 1",
+                FenError::InvalidEnPassant("i3".to_string()),
+            ),
+            //A well-formed target that doesn't fit the position
+            (
+                " w KQkq e6 0 1",
+                FenError::InvalidEnPassant("e6".to_string()),
+            ),
+            (
+                " w KQkq - -1 1",
+                FenError::InvalidHalfmoveClock("-1".to_string()),
+            ),
+            (
+                " w KQkq - 0 0",
+                FenError::InvalidFullmoveNumber("0".to_string()),
+            ),
+            (" w KQkq - 0 1 extra", FenError::TooManyFields(7)),
+        ];
+        for (fields, error) in cases {
+            let fen = format!("{}{}", placement, fields);
+            assert_eq!(fen.parse::<Board>(), Err(error), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn king_counts() {
+        assert_eq!(
+            "8/8/8/8/8/8/8/4K3 w - - 0 1".parse::<Board>(),
+            Err(FenError::WrongKingCount {
+                color: Color::Black,
+                count: 0
+            })
+        );
+        assert_eq!(
+            "k6k/8/8/8/8/8/8/4K3 w - - 0 1".parse::<Board>(),
+            Err(FenError::WrongKingCount {
+                color: Color::Black,
+                count: 2
+            })
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::iter::FusedIterator;
+
+//Files run from a to h, left to right from White's side
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum File {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+}
+
+//Ranks are numbered from White's side, so White's pieces start on the first two
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rank {
+    First,
+    Second,
+    Third,
+    Fourth,
+    Fifth,
+    Sixth,
+    Seventh,
+    Eighth,
+}
+
+impl File {
+    pub const ALL: [File; 8] = [
+        File::A,
+        File::B,
+        File::C,
+        File::D,
+        File::E,
+        File::F,
+        File::G,
+        File::H,
+    ];
+
+    //None for anything past the h file
+    pub const fn new(index: usize) -> Option<File> {
+        if index < 8 {
+            Some(File::ALL[index])
+        } else {
+            None
+        }
+    }
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    //The file the given number of files to the right, or left for negative numbers
+    pub fn offset(self, files: isize) -> Option<File> {
+        File::new(self.index().checked_add_signed(files)?)
+    }
+
+    pub fn from_char(c: char) -> Option<File> {
+        File::ALL.into_iter().find(|file| file.to_char() == c)
+    }
+
+    pub fn to_char(self) -> char {
+        (b'a' + self as u8) as char
+    }
+}
+
+impl Rank {
+    pub const ALL: [Rank; 8] = [
+        Rank::First,
+        Rank::Second,
+        Rank::Third,
+        Rank::Fourth,
+        Rank::Fifth,
+        Rank::Sixth,
+        Rank::Seventh,
+        Rank::Eighth,
+    ];
+
+    //None for anything past the eighth rank
+    pub const fn new(index: usize) -> Option<Rank> {
+        if index < 8 {
+            Some(Rank::ALL[index])
+        } else {
+            None
+        }
+    }
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    //The rank the given number of ranks up the board, or down for negative numbers
+    pub fn offset(self, ranks: isize) -> Option<Rank> {
+        Rank::new(self.index().checked_add_signed(ranks)?)
+    }
+
+    pub fn from_char(c: char) -> Option<Rank> {
+        Rank::ALL.into_iter().find(|rank| rank.to_char() == c)
+    }
+
+    pub fn to_char(self) -> char {
+        (b'1' + self as u8) as char
+    }
+}
+
+//One of the 64 squares, numbered from a1 = 0 along the ranks to h8 = 63 so
+//that the index is also the square's bit in a bitboard. Squares can only be
+//made through the checked constructors, so every Square is on the board.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Square(u8);
+
+//Names every square as a constant, Square::E4 and so on
+macro_rules! square_constants {
+    ($($name:ident = $index:expr),* $(,)?) => {
+        impl Square {
+            $(pub const $name: Square = Square($index);)*
+        }
+    };
+}
+
+square_constants! {
+    A1 = 0, B1 = 1, C1 = 2, D1 = 3, E1 = 4, F1 = 5, G1 = 6, H1 = 7,
+    A2 = 8, B2 = 9, C2 = 10, D2 = 11, E2 = 12, F2 = 13, G2 = 14, H2 = 15,
+    A3 = 16, B3 = 17, C3 = 18, D3 = 19, E3 = 20, F3 = 21, G3 = 22, H3 = 23,
+    A4 = 24, B4 = 25, C4 = 26, D4 = 27, E4 = 28, F4 = 29, G4 = 30, H4 = 31,
+    A5 = 32, B5 = 33, C5 = 34, D5 = 35, E5 = 36, F5 = 37, G5 = 38, H5 = 39,
+    A6 = 40, B6 = 41, C6 = 42, D6 = 43, E6 = 44, F6 = 45, G6 = 46, H6 = 47,
+    A7 = 48, B7 = 49, C7 = 50, D7 = 51, E7 = 52, F7 = 53, G7 = 54, H7 = 55,
+    A8 = 56, B8 = 57, C8 = 58, D8 = 59, E8 = 60, F8 = 61, G8 = 62, H8 = 63,
+}
+
+impl Square {
+    //None for indices of 64 and up
+    pub const fn new(index: usize) -> Option<Square> {
+        if index < 64 {
+            Some(Square(index as u8))
+        } else {
+            None
+        }
+    }
+
+    pub const fn from_file_rank(file: File, rank: Rank) -> Square {
+        Square((rank as u8) * 8 + file as u8)
+    }
+
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub const fn file(self) -> File {
+        File::ALL[(self.0 % 8) as usize]
+    }
+
+    pub const fn rank(self) -> Rank {
+        Rank::ALL[(self.0 / 8) as usize]
+    }
+
+    //The square the given number of files right and ranks up, if that is
+    //still on the board
+    pub fn offset(self, files: isize, ranks: isize) -> Option<Square> {
+        let file = self.file().offset(files)?;
+        let rank = self.rank().offset(ranks)?;
+        Some(Square::from_file_rank(file, rank))
+    }
+
+    //The same square seen from Black's side, so e2 becomes e7
+    pub const fn flip(self) -> Square {
+        Square(self.0 ^ 56)
+    }
+
+    //Every square from a1 to h8, rank by rank
+    pub fn all() -> impl DoubleEndedIterator<Item = Square> + ExactSizeIterator + FusedIterator {
+        (0..64).map(Square)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{File, Rank, Square};
+
+    #[test]
+    fn files_and_ranks() {
+        assert_eq!(File::new(4), Some(File::E));
+        assert_eq!(File::new(8), None);
+        assert_eq!(Rank::new(0), Some(Rank::First));
+        assert_eq!(Rank::new(8), None);
+        assert_eq!(File::A.offset(-1), None);
+        assert_eq!(File::G.offset(1), Some(File::H));
+        assert_eq!(Rank::Eighth.offset(1), None);
+        assert_eq!(File::from_char('c'), Some(File::C));
+        assert_eq!(File::from_char('i'), None);
+        assert_eq!(Rank::from_char('8'), Some(Rank::Eighth));
+        assert_eq!(Rank::from_char('9'), None);
+        assert_eq!((File::H.to_char(), Rank::First.to_char()), ('h', '1'));
+    }
+
+    #[test]
+    fn square_indices() {
+        assert_eq!(Square::new(0), Some(Square::A1));
+        assert_eq!(Square::new(63), Some(Square::H8));
+        assert_eq!(Square::new(64), None);
+        assert_eq!(Square::from_file_rank(File::E, Rank::Fourth), Square::E4);
+        assert_eq!(
+            (Square::E4.file(), Square::E4.rank()),
+            (File::E, Rank::Fourth)
+        );
+        assert_eq!(Square::E2.flip(), Square::E7);
+        for (i, square) in Square::all().enumerate() {
+            assert_eq!(square.index(), i);
+            assert_eq!(Square::from_file_rank(square.file(), square.rank()), square);
+        }
+    }
+
+    #[test]
+    fn offsets_stay_on_the_board() {
+        assert_eq!(Square::G1.offset(-1, 2), Some(Square::F3));
+        assert_eq!(Square::G1.offset(2, 1), None);
+        assert_eq!(Square::A8.offset(0, 1), None);
+        assert_eq!(Square::A1.offset(-1, 0), None);
+        //Knight jumps from the corner
+        let jumps: Vec<Square> = [(1, 2), (2, 1), (-1, 2), (1, -2)]
+            .into_iter()
+            .filter_map(|(files, ranks)| Square::A1.offset(files, ranks))
+            .collect();
+        assert_eq!(jumps, [Square::B3, Square::C2]);
+    }
+}
@@ -0,0 +1,293 @@
+use std::fmt::Display;
+
+use super::{Board, Color, Piece, PieceType, Rank, Square};
+use crate::bitboard::{self, Squares};
+
+//Something that makes a position impossible to reach in a real game
+#[derive(Clone, Debug, PartialEq)]
+pub enum PositionIssue {
+    WrongKingCount { color: Color, count: usize },
+    PawnOnBackRank(Square),
+    TooManyPawns { color: Color, count: usize },
+    //More pieces beyond the starting set than there are missing pawns to
+    //have promoted into them
+    TooManyPromotedPieces { color: Color, count: usize },
+    KingsAdjacent,
+    //The side that just moved left its king in check
+    OpponentInCheck(Color),
+    //A castling right without the king and rook on their starting squares
+    ImpossibleCastling { color: Color, kingside: bool },
+    //The target isn't a square a pawn could just have skipped over
+    ImpossibleEnPassant(Square),
+}
+
+impl Display for PositionIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PositionIssue::WrongKingCount { color, count } => {
+                write!(f, "{} must have exactly one king but has {}", color, count)
+            }
+            PositionIssue::PawnOnBackRank(square) => write!(f, "there is a pawn on {}", square),
+            PositionIssue::TooManyPawns { color, count } => {
+                write!(f, "{} has {} pawns", color, count)
+            }
+            PositionIssue::TooManyPromotedPieces { color, count } => write!(
+                f,
+                "{} has {} promoted pieces but not enough missing pawns",
+                color, count
+            ),
+            PositionIssue::KingsAdjacent => write!(f, "the kings are next to each other"),
+            PositionIssue::OpponentInCheck(color) => {
+                write!(f, "{} is in check but it is not their move", color)
+            }
+            PositionIssue::ImpossibleCastling { color, kingside } => write!(
+                f,
+                "{} can't castle {} without the king and rook at home",
+                color,
+                if *kingside { "kingside" } else { "queenside" }
+            ),
+            PositionIssue::ImpossibleEnPassant(square) => {
+                write!(f, "no pawn can be captured en passant on {}", square)
+            }
+        }
+    }
+}
+
+impl Board {
+    //Every reason the position couldn't have come up in a game, empty if
+    //there are none. Positions from FEN or the builder are worth checking
+    //before searching them, as the move generator assumes one king each.
+    pub fn validate(&self) -> Vec<PositionIssue> {
+        let mut issues = Vec::new();
+        for color in [Color::White, Color::Black] {
+            let count = self.pieces(PieceType::King, color).count_ones() as usize;
+            if count != 1 {
+                issues.push(PositionIssue::WrongKingCount { color, count });
+            }
+        }
+
+        let pawns = self.pieces[PieceType::Pawn as usize];
+        let back_ranks = 0xFF00_0000_0000_00FF;
+        issues.extend(Squares(pawns & back_ranks).map(PositionIssue::PawnOnBackRank));
+        for color in [Color::White, Color::Black] {
+            issues.extend(material_issue(self, color));
+        }
+
+        let white_king = Squares(self.pieces(PieceType::King, Color::White)).next();
+        let black_king = Squares(self.pieces(PieceType::King, Color::Black)).next();
+        if let (Some(white_king), Some(black_king)) = (white_king, black_king) {
+            if bitboard::king_attacks(white_king) & bitboard::bit(black_king) != 0 {
+                issues.push(PositionIssue::KingsAdjacent);
+            }
+        }
+        let opponent = self.current_turn.opposite();
+        if self.is_in_check(opponent) {
+            issues.push(PositionIssue::OpponentInCheck(opponent));
+        }
+
+        for color in [Color::White, Color::Black] {
+            for kingside in [true, false] {
+                let has_right = if kingside {
+                    self.castling_rights.kingside(color)
+                } else {
+                    self.castling_rights.queenside(color)
+                };
+                if has_right && !castling_pieces_at_home(self, color, kingside) {
+                    issues.push(PositionIssue::ImpossibleCastling { color, kingside });
+                }
+            }
+        }
+        if let Some(target) = self.en_passant {
+            if !en_passant_possible(self, target) {
+                issues.push(PositionIssue::ImpossibleEnPassant(target));
+            }
+        }
+        issues
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_empty()
+    }
+}
+
+//Every piece beyond two knights, bishops and rooks and one queen has to be a
+//promoted pawn, so there can't be more of them than pawns have gone missing
+fn material_issue(board: &Board, color: Color) -> Option<PositionIssue> {
+    let count = |piece_type| board.pieces(piece_type, color).count_ones() as usize;
+    let pawns = count(PieceType::Pawn);
+    if pawns > 8 {
+        return Some(PositionIssue::TooManyPawns {
+            color,
+            count: pawns,
+        });
+    }
+    let promoted = count(PieceType::Knight).saturating_sub(2)
+        + count(PieceType::Bishop).saturating_sub(2)
+        + count(PieceType::Rook).saturating_sub(2)
+        + count(PieceType::Queen).saturating_sub(1);
+    if promoted > 8 - pawns {
+        return Some(PositionIssue::TooManyPromotedPieces {
+            color,
+            count: promoted,
+        });
+    }
+    None
+}
+
+fn castling_pieces_at_home(board: &Board, color: Color, kingside: bool) -> bool {
+    let (king, rook) = match (color, kingside) {
+        (Color::White, true) => (Square::E1, Square::H1),
+        (Color::White, false) => (Square::E1, Square::A1),
+        (Color::Black, true) => (Square::E8, Square::H8),
+        (Color::Black, false) => (Square::E8, Square::A8),
+    };
+    board.get_piece(king) == Some(Piece::new(color, PieceType::King))
+        && board.get_piece(rook) == Some(Piece::new(color, PieceType::Rook))
+}
+
+//The target has to be empty, on the square behind a pawn of the side that
+//just moved, with the square the pawn came from empty as well
+pub(super) fn en_passant_possible(board: &Board, target: Square) -> bool {
+    let (rank, forward) = match board.current_turn {
+        Color::White => (Rank::Sixth, -1),
+        Color::Black => (Rank::Third, 1),
+    };
+    let moved = board.current_turn.opposite();
+    let (Some(pawn), Some(origin)) = (target.offset(0, forward), target.offset(0, -forward)) else {
+        return false;
+    };
+    target.rank() == rank
+        && board.get_piece(target).is_none()
+        && board.get_piece(origin).is_none()
+        && board.get_piece(pawn) == Some(Piece::new(moved, PieceType::Pawn))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PositionIssue;
+    use crate::board::{Board, Color, Square};
+
+    fn issues(fen: &str) -> Vec<PositionIssue> {
+        Board::board_from_fen_string(fen.to_string()).validate()
+    }
+
+    #[test]
+    fn legal_positions_have_no_issues() {
+        assert!(Board::construct_board().is_valid());
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3",
+            //Nine queens after promoting every pawn
+            "4k3/8/8/8/8/QQQQQQQQ/8/Q3K3 b - - 0 1",
+        ] {
+            assert_eq!(issues(fen), vec![], "{}", fen);
+        }
+    }
+
+    #[test]
+    fn kings_and_pawns() {
+        assert_eq!(
+            issues("4k3/8/8/8/8/8/8/k3K2k w - - 0 1"),
+            vec![PositionIssue::WrongKingCount {
+                color: Color::Black,
+                count: 3
+            }]
+        );
+        assert_eq!(
+            issues("4k3/8/8/8/8/8/8/8 w - - 0 1"),
+            vec![PositionIssue::WrongKingCount {
+                color: Color::White,
+                count: 0
+            }]
+        );
+        assert_eq!(
+            issues("p3k3/8/8/8/8/8/8/P3K2p w - - 0 1"),
+            vec![
+                PositionIssue::PawnOnBackRank(Square::A1),
+                PositionIssue::PawnOnBackRank(Square::H1),
+                PositionIssue::PawnOnBackRank(Square::A8),
+            ]
+        );
+    }
+
+    #[test]
+    fn too_much_material() {
+        assert_eq!(
+            issues("4k3/8/8/8/8/pppppppp/pppppppp/4K3 w - - 0 1"),
+            vec![PositionIssue::TooManyPawns {
+                color: Color::Black,
+                count: 16
+            }]
+        );
+        //Three knights need a missing pawn, which white doesn't have
+        assert_eq!(
+            issues("4k3/8/8/8/8/8/PPPPPPPP/1NN1KN2 w - - 0 1"),
+            vec![PositionIssue::TooManyPromotedPieces {
+                color: Color::White,
+                count: 1
+            }]
+        );
+        assert_eq!(issues("4k3/8/8/8/8/8/PPPPPPP1/1NN1KN2 w - - 0 1"), vec![]);
+    }
+
+    #[test]
+    fn checks() {
+        assert_eq!(
+            issues("8/8/8/8/8/8/3k4/4K3 w - - 0 1"),
+            vec![
+                PositionIssue::KingsAdjacent,
+                PositionIssue::OpponentInCheck(Color::Black)
+            ]
+        );
+        assert_eq!(
+            issues("4k3/8/8/8/8/8/4R3/4K3 w - - 0 1"),
+            vec![PositionIssue::OpponentInCheck(Color::Black)]
+        );
+        assert_eq!(issues("4k3/8/8/8/8/8/4R3/4K3 b - - 0 1"), vec![]);
+    }
+
+    #[test]
+    fn castling_and_en_passant() {
+        assert_eq!(
+            issues("r3k3/8/8/8/8/8/8/4K2R w KQkq - 0 1"),
+            vec![
+                PositionIssue::ImpossibleCastling {
+                    color: Color::White,
+                    kingside: false
+                },
+                PositionIssue::ImpossibleCastling {
+                    color: Color::Black,
+                    kingside: true
+                },
+            ]
+        );
+        //The pawn has to be in front of the target and its starting square empty
+        assert_eq!(
+            issues("4k3/8/8/3p4/8/8/8/4K3 w - e6 0 1"),
+            vec![PositionIssue::ImpossibleEnPassant(Square::E6)]
+        );
+        assert_eq!(
+            issues("4k3/3p4/8/3p4/8/8/8/4K3 w - d6 0 1"),
+            vec![PositionIssue::ImpossibleEnPassant(Square::D6)]
+        );
+        assert_eq!(
+            issues("4k3/8/8/3p4/8/8/8/4K3 b - d3 0 1"),
+            vec![PositionIssue::ImpossibleEnPassant(Square::D3)]
+        );
+        assert_eq!(issues("4k3/8/8/3p4/8/8/8/4K3 w - d6 0 1"), vec![]);
+    }
+
+    #[test]
+    fn reports_every_issue() {
+        let found = issues("k3k3/8/8/8/8/8/8/p3K2R w Qq - 0 1");
+        assert_eq!(found.len(), 4, "{:?}", found);
+        assert_eq!(
+            found[0],
+            PositionIssue::WrongKingCount {
+                color: Color::Black,
+                count: 2
+            }
+        );
+        assert_eq!(found[1].to_string(), "there is a pawn on a1");
+    }
+}
@@ -0,0 +1,205 @@
+use super::{Board, CastlingRights, Piece, Square};
+use crate::bitboard::{self, Squares};
+use crate::board::{Color, PieceType};
+
+//Random numbers for every feature of a position. A position's key is the xor
+//of the numbers for the features it has, so moves update it by xoring the
+//features that change in and out.
+struct Keys {
+    pieces: [[[u64; 64]; 6]; 2],
+    black_to_move: u64,
+    //White kingside, white queenside, black kingside, black queenside
+    castling: [u64; 4],
+    en_passant_file: [u64; 8],
+}
+
+//SplitMix64, which is good enough to spread the keys out and can run at
+//compile time
+const fn next(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+const fn generate_keys() -> Keys {
+    let mut state = 0x2545_F491_4F6C_DD1D;
+    let mut keys = Keys {
+        pieces: [[[0; 64]; 6]; 2],
+        black_to_move: 0,
+        castling: [0; 4],
+        en_passant_file: [0; 8],
+    };
+    let mut color = 0;
+    while color < 2 {
+        let mut piece_type = 0;
+        while piece_type < 6 {
+            let mut square = 0;
+            while square < 64 {
+                keys.pieces[color][piece_type][square] = next(&mut state);
+                square += 1;
+            }
+            piece_type += 1;
+        }
+        color += 1;
+    }
+    keys.black_to_move = next(&mut state);
+    let mut i = 0;
+    while i < 4 {
+        keys.castling[i] = next(&mut state);
+        i += 1;
+    }
+    let mut file = 0;
+    while file < 8 {
+        keys.en_passant_file[file] = next(&mut state);
+        file += 1;
+    }
+    keys
+}
+
+const KEYS: Keys = generate_keys();
+
+pub(super) fn piece_key(piece: Piece, square: Square) -> u64 {
+    KEYS.pieces[piece.color as usize][piece.piece_type as usize][square.index()]
+}
+
+pub(super) fn side_key() -> u64 {
+    KEYS.black_to_move
+}
+
+pub(super) fn castling_key(rights: CastlingRights) -> u64 {
+    let mut key = 0;
+    for (i, has_right) in [
+        rights.white_kingside,
+        rights.white_queenside,
+        rights.black_kingside,
+        rights.black_queenside,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        if has_right {
+            key ^= KEYS.castling[i];
+        }
+    }
+    key
+}
+
+//The en passant file only counts when a pawn could actually take, so that a
+//double push nobody can capture doesn't stop the position repeating
+pub(super) fn en_passant_key(board: &Board) -> u64 {
+    let Some(target) = board.en_passant else { return 0 };
+    let us = board.current_turn;
+    let capturers =
+        bitboard::pawn_attacks(target, us.opposite()) & board.pieces(PieceType::Pawn, us);
+    if capturers == 0 {
+        return 0;
+    }
+    KEYS.en_passant_file[target.file().index()]
+}
+
+//Works the key out from nothing, which the board only needs when it is set up
+pub(super) fn key(board: &Board) -> u64 {
+    let mut key = castling_key(board.castling_rights) ^ en_passant_key(board);
+    if board.current_turn == Color::Black {
+        key ^= side_key();
+    }
+    for square in Squares(board.occupied()) {
+        let piece = board.get_piece(square).unwrap();
+        key ^= piece_key(piece, square);
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::key;
+    use crate::board::{Board, Move, Square};
+
+    //Xorshift, so the random games are the same on every run
+    fn random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    fn incremental_keys_match_recomputed_keys() {
+        let mut state = 0x1234_5678_9ABC_DEF1;
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            for _ in 0..20 {
+                let mut board: Board = fen.parse().unwrap();
+                let start = board.hash();
+                let mut undos = Vec::new();
+                for _ in 0..60 {
+                    let moves = board.legal_moves();
+                    if moves.is_empty() {
+                        break;
+                    }
+                    let m = moves[random(&mut state) as usize % moves.len()];
+                    undos.push(board.apply_move(m));
+                    assert_eq!(board.hash(), key(&board), "{}", board.to_fen_string());
+                    //A board rebuilt from its FEN has to agree as well
+                    let rebuilt: Board = board.to_fen_string().parse().unwrap();
+                    assert_eq!(board.hash(), rebuilt.hash());
+                }
+                while let Some(undo) = undos.pop() {
+                    board.unmake_move(undo);
+                    assert_eq!(board.hash(), key(&board));
+                }
+                assert_eq!(board.hash(), start);
+            }
+        }
+    }
+
+    #[test]
+    fn transpositions_share_a_key() {
+        let play = |moves: &[&str]| {
+            let mut board = Board::construct_board();
+            for m in moves {
+                board.apply_move(m.parse().unwrap());
+            }
+            board.hash()
+        };
+        assert_eq!(
+            play(&["g1f3", "g8f6", "b1c3"]),
+            play(&["b1c3", "g8f6", "g1f3"])
+        );
+        assert_ne!(play(&["g1f3"]), play(&["b1c3"]));
+        //Same pieces, different side to move
+        assert_ne!(
+            play(&["g1f3", "g8f6", "f3g1", "f6g8"]),
+            play(&["g1f3", "g8f6", "f3g1"])
+        );
+        assert_eq!(
+            play(&["g1f3", "g8f6", "f3g1", "f6g8"]),
+            Board::construct_board().hash()
+        );
+    }
+
+    #[test]
+    fn castling_and_en_passant_change_the_key() {
+        let with_rights: Board = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".parse().unwrap();
+        let without: Board = "r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1".parse().unwrap();
+        assert_ne!(with_rights.hash(), without.hash());
+
+        //A capturable pawn makes a difference, an uncapturable one doesn't
+        let capturable: Board = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1".parse().unwrap();
+        let no_target: Board = "4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1".parse().unwrap();
+        assert_ne!(capturable.hash(), no_target.hash());
+        let lone: Board = "4k3/8/8/3p4/8/8/8/4K3 w - d6 0 1".parse().unwrap();
+        let lone_no_target: Board = "4k3/8/8/3p4/8/8/8/4K3 w - - 0 1".parse().unwrap();
+        assert_eq!(lone.hash(), lone_no_target.hash());
+
+        let mut board = Board::construct_board();
+        board.apply_move(Move::new(Square::E2, Square::E4));
+        assert_eq!(board.hash(), key(&board));
+    }
+}
@@ -0,0 +1,570 @@
+use std::fmt::Display;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+use crate::bitboard::{self, bit, Bitboard, Squares};
+use crate::board::{Board, Color, Move, PieceType, Square};
+use crate::piece_moves;
+
+//Every term is scored twice, once for the middlegame and once for the endgame,
+//and the two are blended by how much material is left on the board
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Score {
+    pub middlegame: i32,
+    pub endgame: i32,
+}
+
+const fn s(middlegame: i32, endgame: i32) -> Score {
+    Score {
+        middlegame,
+        endgame,
+    }
+}
+
+impl Add for Score {
+    type Output = Score;
+
+    fn add(self, other: Score) -> Score {
+        s(
+            self.middlegame + other.middlegame,
+            self.endgame + other.endgame,
+        )
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, other: Score) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Score {
+    type Output = Score;
+
+    fn sub(self, other: Score) -> Score {
+        s(
+            self.middlegame - other.middlegame,
+            self.endgame - other.endgame,
+        )
+    }
+}
+
+impl Neg for Score {
+    type Output = Score;
+
+    fn neg(self) -> Score {
+        s(-self.middlegame, -self.endgame)
+    }
+}
+
+impl Mul<i32> for Score {
+    type Output = Score;
+
+    fn mul(self, n: i32) -> Score {
+        s(self.middlegame * n, self.endgame * n)
+    }
+}
+
+//Each term is from white's point of view, so a positive score favours white
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Evaluation {
+    //From 0 with only kings and pawns left up to MAX_PHASE at the start
+    pub phase: i32,
+    pub material: Score,
+    pub piece_squares: Score,
+    pub mobility: Score,
+    pub pawn_structure: Score,
+    pub king_safety: Score,
+}
+
+const MAX_PHASE: i32 = 24;
+
+impl Evaluation {
+    //Blends a term's middlegame and endgame scores for this position
+    pub fn taper(&self, score: Score) -> i32 {
+        (score.middlegame * self.phase + score.endgame * (MAX_PHASE - self.phase)) / MAX_PHASE
+    }
+
+    pub fn total(&self) -> i32 {
+        self.taper(
+            self.material
+                + self.piece_squares
+                + self.mobility
+                + self.pawn_structure
+                + self.king_safety,
+        )
+    }
+}
+
+impl Display for Evaluation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "{:<15}{:>8}{:>8}{:>8}", "Term", "MG", "EG", "Blend")?;
+        let terms = [
+            ("Material", self.material),
+            ("Piece squares", self.piece_squares),
+            ("Mobility", self.mobility),
+            ("Pawn structure", self.pawn_structure),
+            ("King safety", self.king_safety),
+        ];
+        for (name, score) in terms {
+            writeln!(
+                f,
+                "{:<15}{:>8}{:>8}{:>8}",
+                name,
+                score.middlegame,
+                score.endgame,
+                self.taper(score)
+            )?;
+        }
+        writeln!(
+            f,
+            "Phase {}/{}, total {} for white",
+            self.phase,
+            MAX_PHASE,
+            self.total()
+        )
+    }
+}
+
+//Centipawns from the point of view of the side to move, as search wants them
+pub fn evaluate(board: &Board) -> i32 {
+    let total = board.evaluation().total();
+    match board.current_turn() {
+        Color::White => total,
+        Color::Black => -total,
+    }
+}
+
+impl Board {
+    pub fn evaluation(&self) -> Evaluation {
+        let mut evaluation = Evaluation {
+            phase: phase(self),
+            ..Evaluation::default()
+        };
+        for (color, sign) in [(Color::White, 1), (Color::Black, -1)] {
+            evaluation.material += material(self, color) * sign;
+            evaluation.piece_squares += piece_squares(self, color) * sign;
+            evaluation.mobility += mobility(self, color) * sign;
+            evaluation.pawn_structure += pawn_structure(self, color) * sign;
+            evaluation.king_safety += king_safety(self, color) * sign;
+        }
+        evaluation
+    }
+}
+
+const PIECE_TYPES: [PieceType; 6] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+    PieceType::King,
+];
+
+pub fn piece_value(piece_type: PieceType) -> Score {
+    match piece_type {
+        PieceType::Pawn => s(82, 94),
+        PieceType::Knight => s(337, 281),
+        PieceType::Bishop => s(365, 297),
+        PieceType::Rook => s(477, 512),
+        PieceType::Queen => s(1025, 936),
+        PieceType::King => s(0, 0),
+    }
+}
+
+fn phase(board: &Board) -> i32 {
+    let mut phase = 0;
+    for color in [Color::White, Color::Black] {
+        phase += board.pieces(PieceType::Knight, color).count_ones() as i32;
+        phase += board.pieces(PieceType::Bishop, color).count_ones() as i32;
+        phase += 2 * board.pieces(PieceType::Rook, color).count_ones() as i32;
+        phase += 4 * board.pieces(PieceType::Queen, color).count_ones() as i32;
+    }
+    //Promotions can push it past the starting material
+    phase.min(MAX_PHASE)
+}
+
+fn material(board: &Board, color: Color) -> Score {
+    let mut score = Score::default();
+    for piece_type in PIECE_TYPES {
+        score += piece_value(piece_type) * board.pieces(piece_type, color).count_ones() as i32;
+    }
+    score
+}
+
+//Piece-square tables are written the way white sees the board, with the eighth
+//rank at the top, so white's squares are looked up flipped
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     10,  10,  20,  30,  30,  20,  10,  10,
+      5,   5,  10,  25,  25,  10,   5,   5,
+      0,   0,   0,  20,  20,   0,   0,   0,
+      5,  -5, -10,   0,   0, -10,  -5,   5,
+      5,  10,  10, -20, -20,  10,  10,   5,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+#[rustfmt::skip]
+const PAWN_ENDGAME_TABLE: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     80,  80,  80,  80,  80,  80,  80,  80,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     30,  30,  30,  30,  30,  30,  30,  30,
+     20,  20,  20,  20,  20,  20,  20,  20,
+     10,  10,  10,  10,  10,  10,  10,  10,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   5,  15,  20,  20,  15,   5, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   5,  10,  15,  15,  10,   5, -30,
+    -40, -20,   0,   5,   5,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   5,   5,  10,  10,   5,   5, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,  10,  10,  10,  10,  10,  10, -10,
+    -10,   5,   0,   0,   0,   0,   5, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+];
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+      5,  10,  10,  10,  10,  10,  10,   5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+      0,   0,   0,   5,   5,   0,   0,   0,
+];
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+     -5,   0,   5,   5,   5,   5,   0,  -5,
+      0,   0,   5,   5,   5,   5,   0,  -5,
+    -10,   5,   5,   5,   5,   5,   0, -10,
+    -10,   0,   5,   0,   0,   0,   0, -10,
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+//The king hides behind its pawns while there are pieces around to attack it
+//and heads for the centre once they are gone
+#[rustfmt::skip]
+const KING_TABLE: [i32; 64] = [
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -20, -30, -30, -40, -40, -30, -30, -20,
+    -10, -20, -20, -20, -20, -20, -20, -10,
+     20,  20,   0,   0,   0,   0,  20,  20,
+     20,  30,  10,   0,   0,  10,  30,  20,
+];
+#[rustfmt::skip]
+const KING_ENDGAME_TABLE: [i32; 64] = [
+    -50, -40, -30, -20, -20, -30, -40, -50,
+    -30, -20, -10,   0,   0, -10, -20, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -30,   0,   0,   0,   0, -30, -30,
+    -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+fn piece_square(piece_type: PieceType, color: Color, square: Square) -> Score {
+    let index = match color {
+        Color::White => square.flip().index(),
+        Color::Black => square.index(),
+    };
+    let (middlegame, endgame) = match piece_type {
+        PieceType::Pawn => (&PAWN_TABLE, &PAWN_ENDGAME_TABLE),
+        PieceType::Knight => (&KNIGHT_TABLE, &KNIGHT_TABLE),
+        PieceType::Bishop => (&BISHOP_TABLE, &BISHOP_TABLE),
+        PieceType::Rook => (&ROOK_TABLE, &ROOK_TABLE),
+        PieceType::Queen => (&QUEEN_TABLE, &QUEEN_TABLE),
+        PieceType::King => (&KING_TABLE, &KING_ENDGAME_TABLE),
+    };
+    s(middlegame[index], endgame[index])
+}
+
+fn piece_squares(board: &Board, color: Color) -> Score {
+    let mut score = Score::default();
+    for piece_type in PIECE_TYPES {
+        for square in Squares(board.pieces(piece_type, color)) {
+            score += piece_square(piece_type, color, square);
+        }
+    }
+    score
+}
+
+//Points per move above or below what the piece has on an average square
+fn mobility_weight(piece_type: PieceType) -> (usize, Score) {
+    match piece_type {
+        PieceType::Knight => (4, s(4, 4)),
+        PieceType::Bishop => (6, s(5, 5)),
+        PieceType::Rook => (7, s(2, 4)),
+        PieceType::Queen => (13, s(1, 2)),
+        PieceType::Pawn | PieceType::King => (0, s(0, 0)),
+    }
+}
+
+fn mobility(board: &Board, color: Color) -> Score {
+    let mut score = Score::default();
+    let mut moves: Vec<Move> = Vec::new();
+    for piece_type in [
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+    ] {
+        let (average, weight) = mobility_weight(piece_type);
+        for square in Squares(board.pieces(piece_type, color)) {
+            moves.clear();
+            match piece_type {
+                PieceType::Knight => piece_moves::knight_moves(board, square, color, &mut moves),
+                PieceType::Bishop => piece_moves::bishop_moves(board, square, color, &mut moves),
+                PieceType::Rook => piece_moves::rook_moves(board, square, color, &mut moves),
+                _ => piece_moves::queen_moves(board, square, color, &mut moves),
+            }
+            score += weight * (moves.len() as i32 - average as i32);
+        }
+    }
+    score
+}
+
+const FILE_A: Bitboard = 0x0101_0101_0101_0101;
+const DOUBLED_PAWN: Score = s(-10, -20);
+const ISOLATED_PAWN: Score = s(-15, -20);
+//Indexed by how many ranks the pawn has advanced
+const PASSED_PAWN: [Score; 8] = [
+    s(0, 0),
+    s(5, 10),
+    s(10, 20),
+    s(15, 35),
+    s(25, 60),
+    s(40, 100),
+    s(60, 150),
+    s(0, 0),
+];
+
+fn adjacent_files(file: usize) -> Bitboard {
+    let mut files = 0;
+    if file > 0 {
+        files |= FILE_A << (file - 1);
+    }
+    if file < 7 {
+        files |= FILE_A << (file + 1);
+    }
+    files
+}
+
+//Every square strictly in front of the square from the given side's view
+fn ahead_of(square: Square, color: Color) -> Bitboard {
+    let rank = square.rank().index();
+    match color {
+        Color::White if rank == 7 => 0,
+        Color::White => !0 << ((rank + 1) * 8),
+        Color::Black => (1 << (rank * 8)) - 1,
+    }
+}
+
+fn pawn_structure(board: &Board, color: Color) -> Score {
+    let pawns = board.pieces(PieceType::Pawn, color);
+    let enemy_pawns = board.pieces(PieceType::Pawn, color.opposite());
+    let mut score = Score::default();
+    for file in 0..8 {
+        let on_file = (pawns & (FILE_A << file)).count_ones() as i32;
+        if on_file > 1 {
+            score += DOUBLED_PAWN * (on_file - 1);
+        }
+        if on_file > 0 && pawns & adjacent_files(file) == 0 {
+            score += ISOLATED_PAWN * on_file;
+        }
+    }
+    for square in Squares(pawns) {
+        let file = square.file().index();
+        let front_span = ahead_of(square, color) & ((FILE_A << file) | adjacent_files(file));
+        if enemy_pawns & front_span == 0 {
+            let advanced = match color {
+                Color::White => square.rank().index(),
+                Color::Black => square.flip().rank().index(),
+            };
+            score += PASSED_PAWN[advanced];
+        }
+    }
+    score
+}
+
+const PAWN_SHIELD: Score = s(12, 0);
+const KING_ZONE_ATTACK: Score = s(-8, 0);
+
+//Pawns in front of the king and enemy pieces bearing down on the squares
+//around it. Both only matter while there are pieces left to attack with.
+fn king_safety(board: &Board, color: Color) -> Score {
+    let Some(king) = Squares(board.pieces(PieceType::King, color)).next() else {
+        return Score::default();
+    };
+    let file = king.file().index();
+    let files = (FILE_A << file) | adjacent_files(file);
+    //Only the two ranks right in front of the king count towards the shield
+    let rank = king.rank().index();
+    let shield_ranks: Bitboard = match color {
+        Color::White => 0xFFFF_u64.checked_shl(((rank + 1) * 8) as u32).unwrap_or(0),
+        Color::Black => 0xFFFF << (rank.saturating_sub(2) * 8),
+    };
+    let shield =
+        board.pieces(PieceType::Pawn, color) & files & shield_ranks & ahead_of(king, color);
+    let mut score = PAWN_SHIELD * shield.count_ones() as i32;
+
+    let zone = bitboard::king_attacks(king) | bit(king);
+    let them = color.opposite();
+    let occupied = board.occupied();
+    let mut attacked = 0;
+    for square in Squares(board.pieces(PieceType::Knight, them)) {
+        attacked |= bitboard::knight_attacks(square);
+    }
+    for square in
+        Squares(board.pieces(PieceType::Bishop, them) | board.pieces(PieceType::Queen, them))
+    {
+        attacked |= bitboard::bishop_attacks(square, occupied);
+    }
+    for square in
+        Squares(board.pieces(PieceType::Rook, them) | board.pieces(PieceType::Queen, them))
+    {
+        attacked |= bitboard::rook_attacks(square, occupied);
+    }
+    score += KING_ZONE_ATTACK * (attacked & zone).count_ones() as i32;
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, pawn_structure, ISOLATED_PAWN, MAX_PHASE};
+    use crate::board::{Board, Color};
+
+    fn evaluation(fen: &str) -> super::Evaluation {
+        fen.parse::<Board>().unwrap().evaluation()
+    }
+
+    //Swaps the colours and turns the board upside down
+    fn mirror(fen: &str) -> String {
+        let fields: Vec<&str> = fen.split(' ').collect();
+        let ranks: Vec<String> = fields[0]
+            .split('/')
+            .rev()
+            .map(|rank| {
+                rank.chars()
+                    .map(|c| {
+                        if c.is_uppercase() {
+                            c.to_ascii_lowercase()
+                        } else {
+                            c.to_ascii_uppercase()
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let side = if fields[1] == "w" { "b" } else { "w" };
+        format!(
+            "{} {} - - {} {}",
+            ranks.join("/"),
+            side,
+            fields[4],
+            fields[5]
+        )
+    }
+
+    #[test]
+    fn start_position_is_balanced() {
+        let board = Board::construct_board();
+        let evaluation = board.evaluation();
+        assert_eq!(evaluation.phase, MAX_PHASE);
+        assert_eq!(evaluation.total(), 0);
+        assert_eq!(evaluate(&board), 0);
+    }
+
+    #[test]
+    fn evaluation_is_symmetric() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w - - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w - - 1 8",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ] {
+            let board: Board = fen.parse().unwrap();
+            let mirrored: Board = mirror(fen).parse().unwrap();
+            assert_eq!(
+                board.evaluation().total(),
+                -mirrored.evaluation().total(),
+                "{}",
+                fen
+            );
+            assert_eq!(evaluate(&board), evaluate(&mirrored), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn extra_material_is_better() {
+        //White is a knight up
+        let evaluation = evaluation("rnbqkb1r/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert!(evaluation.material.middlegame > 300);
+        assert!(evaluation.total() > 250);
+        let board: Board = "rnbqkb1r/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"
+            .parse()
+            .unwrap();
+        assert!(evaluate(&board) < -250);
+    }
+
+    #[test]
+    fn pawn_structure_terms() {
+        //Doubled and isolated pawns on the a file against a healthy pair
+        let bad = evaluation("4k3/8/8/8/8/P7/P4PP1/4K3 w - - 0 1").pawn_structure;
+        let good = evaluation("4k3/8/8/8/8/8/P4PP1/4K3 w - - 0 1").pawn_structure;
+        assert!(bad.endgame < good.endgame);
+
+        //A passed pawn is worth more the further it has gone
+        let far = evaluation("4k3/8/1P6/8/8/8/8/4K3 w - - 0 1").pawn_structure;
+        let near = evaluation("4k3/8/8/8/8/1P6/8/4K3 w - - 0 1").pawn_structure;
+        assert!(far.endgame > near.endgame);
+
+        //A pawn with an enemy pawn ahead of it on an adjacent file isn't passed
+        let board: Board = "4k3/8/2p5/8/8/1P6/8/4K3 w - - 0 1".parse().unwrap();
+        assert_eq!(pawn_structure(&board, Color::White), ISOLATED_PAWN);
+    }
+
+    #[test]
+    fn king_safety_terms() {
+        let sheltered = evaluation("r5k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").king_safety;
+        let exposed = evaluation("r5k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").king_safety;
+        assert_eq!(sheltered.middlegame, 0);
+        assert!(exposed.middlegame < 0);
+        assert_eq!(exposed.endgame, 0);
+    }
+
+    #[test]
+    fn mobility_rewards_active_pieces() {
+        let active = evaluation("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1").mobility;
+        let cornered = evaluation("4k3/8/8/8/8/8/8/N3K3 w - - 0 1").mobility;
+        assert!(active.middlegame > cornered.middlegame);
+    }
+
+    #[test]
+    fn breakdown_is_printable() {
+        let text = Board::construct_board().evaluation().to_string();
+        assert!(text.contains("Material"));
+        assert!(text.contains("King safety"));
+        assert!(text.ends_with("total 0 for white\n"));
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::board::{Board, Color, IllegalMove, Move, UndoInfo};
+use crate::outcome::GameOutcome;
+use crate::pgn::PgnGame;
+
+//The seven tags every PGN game has to carry, in the order they are exported
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+//The PGN standard asks for lines shorter than 80 characters
+const MAX_LINE_LENGTH: usize = 79;
+
+//A record of a game played from some starting position
+#[derive(Clone, Debug)]
+pub struct Game {
+    start: Board,
+    board: Board,
+    moves: Vec<Move>,
+    undos: Vec<UndoInfo>,
+    tags: Vec<(String, String)>,
+    //Keyed by the number of moves played when the comment was made, so 0 is
+    //a comment before the first move
+    comments: HashMap<usize, String>,
+}
+
+impl Game {
+    pub fn new() -> Game {
+        Game::from_board(Board::construct_board())
+    }
+
+    pub fn from_board(board: Board) -> Game {
+        Game {
+            start: board.clone(),
+            board,
+            moves: Vec::new(),
+            undos: Vec::new(),
+            tags: Vec::new(),
+            comments: HashMap::new(),
+        }
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    //The current position
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn play(&mut self, m: Move) -> Result<(), IllegalMove> {
+        let undo = self.board.make_move(m)?;
+        self.moves.push(m);
+        self.undos.push(undo);
+        Ok(())
+    }
+
+    //Takes back the last move along with any comment made after it
+    pub fn undo(&mut self) -> Option<Move> {
+        let undo = self.undos.pop()?;
+        self.comments.remove(&self.moves.len());
+        self.board.unmake_move(undo);
+        self.moves.pop()
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    //Attaches a comment to the last move played, replacing any already there
+    pub fn comment(&mut self, text: &str) {
+        self.comments.insert(self.moves.len(), text.to_string());
+    }
+
+    //A Result tag wins, for games ended by resignation or agreement, otherwise
+    //the result follows from the position
+    pub fn result(&self) -> &str {
+        if let Some(result) = self.tag("Result").filter(|r| *r != "*") {
+            return result;
+        }
+        match self.board.outcome() {
+            Some(GameOutcome::Checkmate {
+                winner: Color::White,
+            }) => "1-0",
+            Some(GameOutcome::Checkmate {
+                winner: Color::Black,
+            }) => "0-1",
+            Some(_) => "1/2-1/2",
+            None => "*",
+        }
+    }
+
+    pub fn to_pgn(&self) -> String {
+        self.to_string()
+    }
+
+    fn tag_pairs(&self) -> Vec<(&str, String)> {
+        let mut tags: Vec<(&str, String)> = SEVEN_TAG_ROSTER
+            .iter()
+            .map(|(name, default)| (*name, self.tag(name).unwrap_or(default).to_string()))
+            .collect();
+        tags[6].1 = self.result().to_string();
+        if self.start != Board::construct_board() {
+            tags.push(("SetUp", "1".to_string()));
+            tags.push(("FEN", self.start.to_fen_string()));
+        }
+        for (name, value) in &self.tags {
+            if !tags.iter().any(|(tag, _)| tag == name) {
+                tags.push((name, value.clone()));
+            }
+        }
+        tags
+    }
+
+    //Every move number, move, comment word and the result as separate tokens so
+    //that lines can be broken between any of them
+    fn movetext(&self) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let mut board = self.start.clone();
+        let push_comment = |tokens: &mut Vec<String>, ply: usize| {
+            let Some(comment) = self.comments.get(&ply) else { return false };
+            //A comment can't contain its own closing brace
+            let comment = comment.replace('}', "");
+            let words: Vec<&str> = comment.split_whitespace().collect();
+            let last = words.len().saturating_sub(1);
+            for (i, word) in words.iter().enumerate() {
+                let open = if i == 0 { "{" } else { "" };
+                let close = if i == last { "}" } else { "" };
+                tokens.push(format!("{}{}{}", open, word, close));
+            }
+            if words.is_empty() {
+                tokens.push("{}".to_string());
+            }
+            true
+        };
+
+        let mut after_comment = push_comment(&mut tokens, 0);
+        for (i, m) in self.moves.iter().enumerate() {
+            match board.current_turn() {
+                Color::White => tokens.push(format!("{}.", board.fullmove_number())),
+                //Black's moves only get a number when something interrupts the
+                //move pair, which includes a game starting with black to move
+                Color::Black if i == 0 || after_comment => {
+                    tokens.push(format!("{}...", board.fullmove_number()))
+                }
+                Color::Black => (),
+            }
+            tokens.push(m.to_san(&board).expect("the game only holds legal moves"));
+            board.apply_move(*m);
+            after_comment = push_comment(&mut tokens, i + 1);
+        }
+        tokens.push(self.result().to_string());
+        tokens
+    }
+}
+
+impl Default for Game {
+    fn default() -> Game {
+        Game::new()
+    }
+}
+
+//Only the main line of an imported game is kept; the first comment after each
+//move is kept with it
+impl From<PgnGame> for Game {
+    fn from(pgn: PgnGame) -> Game {
+        let mut game = Game::from_board(pgn.start);
+        for (name, value) in &pgn.tags {
+            if name != "SetUp" && name != "FEN" {
+                game.set_tag(name, value);
+            }
+        }
+        if let Some(comment) = pgn.comments.first() {
+            game.comment(comment);
+        }
+        for m in pgn.moves {
+            //Moves in a PgnGame have already been checked
+            let undo = game.board.apply_move(m.m);
+            game.moves.push(m.m);
+            game.undos.push(undo);
+            if let Some(comment) = m.comments.first() {
+                game.comment(comment);
+            }
+        }
+        if pgn.result != "*" {
+            game.set_tag("Result", &pgn.result);
+        }
+        game
+    }
+}
+
+//Writes the game as PGN in export format
+impl Display for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (name, value) in self.tag_pairs() {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{} \"{}\"]", name, value)?;
+        }
+        writeln!(f)?;
+
+        let mut line = String::new();
+        for token in self.movetext() {
+            if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE_LENGTH {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Game;
+    use crate::board::Board;
+    use crate::pgn::PgnGame;
+
+    fn play(game: &mut Game, moves: &str) {
+        for san in moves.split_whitespace() {
+            let m = game.board().parse_san(san).unwrap();
+            game.play(m).unwrap();
+        }
+    }
+
+    #[test]
+    fn exports_tags_and_moves() {
+        let mut game = Game::new();
+        game.set_tag("White", "Morphy, \"Paul\"");
+        game.set_tag("Black", "Duke Karl / Count Isouard");
+        game.set_tag("Event", "Paris");
+        game.set_tag("Opening", "Philidor Defence");
+        play(&mut game, "e4 e5 Nf3 d6");
+        game.comment("Philidor's defence");
+        play(&mut game, "d4 Bg4");
+
+        assert_eq!(
+            game.to_pgn(),
+            "[Event \"Paris\"]\n\
+             [Site \"?\"]\n\
+             [Date \"????.??.??\"]\n\
+             [Round \"?\"]\n\
+             [White \"Morphy, \\\"Paul\\\"\"]\n\
+             [Black \"Duke Karl / Count Isouard\"]\n\
+             [Result \"*\"]\n\
+             [Opening \"Philidor Defence\"]\n\
+             \n\
+             1. e4 e5 2. Nf3 d6 {Philidor's defence} 3. d4 Bg4 *\n"
+        );
+    }
+
+    #[test]
+    fn numbers_black_moves_after_comments_and_setup() {
+        let board: Board = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30".parse().unwrap();
+        let mut game = Game::from_board(board);
+        play(&mut game, "Kd7");
+        game.comment("waiting");
+        play(&mut game, "e4");
+        game.comment("pushing");
+        play(&mut game, "Kc6");
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 30\"]\n"));
+        assert!(pgn.ends_with("\n30... Kd7 {waiting} 31. e4 {pushing} 31... Kc6 *\n"));
+    }
+
+    #[test]
+    fn result_comes_from_the_position_or_tag() {
+        let mut game = Game::new();
+        play(&mut game, "f3 e5 g4 Qh4#");
+        assert_eq!(game.result(), "0-1");
+        assert!(game.to_pgn().contains("[Result \"0-1\"]"));
+        assert!(game.to_pgn().ends_with("2. g4 Qh4# 0-1\n"));
+
+        let mut game = Game::new();
+        play(&mut game, "e4");
+        game.set_tag("Result", "1-0");
+        assert!(game.to_pgn().ends_with("1. e4 1-0\n"));
+    }
+
+    #[test]
+    fn wraps_long_games() {
+        let mut game = Game::new();
+        for _ in 0..10 {
+            play(&mut game, "Nf3 Nf6 Ng1 Ng8");
+        }
+        game.set_tag("Result", "1/2-1/2");
+        let pgn = game.to_pgn();
+        let movetext: Vec<&str> = pgn.split("\n\n").nth(1).unwrap().lines().collect();
+        assert!(movetext.len() > 1);
+        assert!(movetext.iter().all(|line| line.len() < 80));
+        assert!(movetext.iter().all(|line| !line.ends_with(' ')));
+    }
+
+    #[test]
+    fn undo_takes_back_moves_and_comments() {
+        let mut game = Game::new();
+        play(&mut game, "e4 e5");
+        game.comment("symmetrical");
+        assert_eq!(game.undo().map(|m| m.to_string()), Some("e7e5".to_string()));
+        assert_eq!(game.moves().len(), 1);
+        assert!(!game.to_pgn().contains("symmetrical"));
+        assert_eq!(game.undo().map(|m| m.to_string()), Some("e2e4".to_string()));
+        assert_eq!(game.undo(), None);
+        assert_eq!(game.board(), &Board::construct_board());
+    }
+
+    #[test]
+    fn round_trips_through_pgn_import() {
+        let mut game = Game::new();
+        game.set_tag("White", "A");
+        game.comment("Before the first move");
+        play(
+            &mut game,
+            "d4 d5 c4 dxc4 e4 b5 a4 c6 axb5 cxb5 Qf3 Nc6 Qxf7+",
+        );
+        game.comment("wins a pawn");
+
+        let imported: PgnGame = game.to_pgn().parse().unwrap();
+        let imported = Game::from(imported);
+        assert_eq!(imported.moves(), game.moves());
+        assert_eq!(imported.board(), game.board());
+        assert_eq!(imported.to_pgn(), game.to_pgn());
+    }
+}
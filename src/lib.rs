@@ -0,0 +1,35 @@
+//A chess engine usable as a library. The board, moves and notation are
+//re-exported here; the modules behind them are private so their layout can
+//change without breaking anyone depending on the crate.
+mod bitboard;
+mod board;
+mod eval;
+mod game;
+mod notation;
+mod outcome;
+mod perft;
+mod pgn;
+mod piece_moves;
+mod render;
+mod san;
+mod search;
+mod tt;
+
+//Front ends that talk to a user or a GUI over any reader and writer
+pub mod repl;
+pub mod uci;
+
+pub use bitboard::{Bitboard, Squares};
+pub use board::fen::FenError;
+pub use board::{
+    Board, BoardBuilder, CastlingRights, Color, File, IllegalMove, Move, Piece, PieceType,
+    PositionError, PositionIssue, Rank, Square, UndoInfo,
+};
+pub use eval::{evaluate, Evaluation, Score};
+pub use game::Game;
+pub use notation::{ParseMoveError, ParseSquareError};
+pub use outcome::GameOutcome;
+pub use pgn::{PgnError, PgnGame, PgnMove};
+pub use render::{Diagram, RenderOptions, RenderStyle};
+pub use san::SanError;
+pub use search::{mate_in, SearchInfo, SearchLimits, MATE, MAX_DEPTH};
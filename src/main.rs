@@ -1,7 +1,80 @@
-mod board;
-mod piece_moves;
+use std::time::Instant;
+
+use chess::{repl, uci, Board};
 
-#[allow(dead_code)]
 fn main() {
-    let _board = board::Board::construct_board();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(|s| s.as_str()) {
+        Some("play") => play(&args[1..]),
+        Some("perft") => perft(&args[1..]),
+        Some("uci") => uci(),
+        Some(command) => {
+            eprintln!("Unknown command: {}", command);
+            usage();
+        }
+        None => play(&[]),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: chess [play [fen]]");
+    eprintln!("       chess perft <depth> [fen]");
+    eprintln!("       chess uci");
+    std::process::exit(1);
+}
+
+fn parse_fen_or_exit(fen: &str) -> Board {
+    match fen.parse::<Board>() {
+        Ok(board) => board,
+        Err(e) => {
+            eprintln!("Invalid FEN: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn play(args: &[String]) {
+    let board = if args.is_empty() {
+        Board::construct_board()
+    } else {
+        parse_fen_or_exit(&args.join(" "))
+    };
+    let stdin = std::io::stdin();
+    if let Err(e) = repl::run(board, stdin.lock(), &mut std::io::stdout()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn uci() {
+    let stdin = std::io::stdin();
+    if let Err(e) = uci::run(stdin.lock(), std::io::stdout()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+//Prints the node count below each move followed by the total, in the same
+//format as other engines so the output can be diffed against them
+fn perft(args: &[String]) {
+    let Some(depth) = args.first().and_then(|d| d.parse().ok()) else { usage() };
+    let board = if args.len() > 1 {
+        parse_fen_or_exit(&args[1..].join(" "))
+    } else {
+        Board::construct_board()
+    };
+
+    let start = Instant::now();
+    let divide = board.divide(depth);
+    let mut total = 0;
+    for (m, nodes) in &divide {
+        println!("{}: {}", m, nodes);
+        total += nodes;
+    }
+    if depth == 0 {
+        total = 1;
+    }
+    println!();
+    println!("Nodes searched: {}", total);
+    println!("Time: {:.3}s", start.elapsed().as_secs_f64());
 }
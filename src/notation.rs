@@ -0,0 +1,215 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::board::{File, Move, PieceType, Rank, Square};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseSquareError {
+    //Not a letter followed by a digit
+    InvalidFormat(String),
+    //Well formed but off the board, like i4 or e9
+    OutOfRange(String),
+}
+
+impl Display for ParseSquareError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseSquareError::InvalidFormat(s) => write!(f, "'{}' is not a square name", s),
+            ParseSquareError::OutOfRange(s) => write!(f, "{} is not on the board", s),
+        }
+    }
+}
+
+impl std::error::Error for ParseSquareError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseMoveError {
+    WrongLength(String),
+    InvalidSquare(ParseSquareError),
+    UnknownPromotion(char),
+}
+
+impl Display for ParseMoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseMoveError::WrongLength(s) => {
+                write!(f, "'{}' should look like e2e4 or e7e8q", s)
+            }
+            ParseMoveError::InvalidSquare(e) => write!(f, "{}", e),
+            ParseMoveError::UnknownPromotion(c) => {
+                write!(f, "'{}' is not a piece a pawn can promote to", c)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseMoveError {}
+
+impl From<ParseSquareError> for ParseMoveError {
+    fn from(e: ParseSquareError) -> ParseMoveError {
+        ParseMoveError::InvalidSquare(e)
+    }
+}
+
+impl Display for File {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl Display for Rank {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+//Squares are written as a file letter and a rank number, like e2
+impl Display for Square {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}{}", self.file(), self.rank())
+    }
+}
+
+impl FromStr for Square {
+    type Err = ParseSquareError;
+
+    fn from_str(s: &str) -> Result<Square, ParseSquareError> {
+        let chars: Vec<char> = s.chars().collect();
+        let [file, rank] = chars[..] else {
+            return Err(ParseSquareError::InvalidFormat(s.to_string()));
+        };
+        if !file.is_ascii_lowercase() || !rank.is_ascii_digit() {
+            return Err(ParseSquareError::InvalidFormat(s.to_string()));
+        }
+        match (File::from_char(file), Rank::from_char(rank)) {
+            (Some(file), Some(rank)) => Ok(Square::from_file_rank(file, rank)),
+            _ => Err(ParseSquareError::OutOfRange(s.to_string())),
+        }
+    }
+}
+
+//UCI long algebraic notation: the origin and destination squares followed by
+//the promotion piece in lower case. Castling is written as the king's move.
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        match self.promotion {
+            Some(PieceType::Queen) => write!(f, "q"),
+            Some(PieceType::Rook) => write!(f, "r"),
+            Some(PieceType::Bishop) => write!(f, "b"),
+            Some(PieceType::Knight) => write!(f, "n"),
+            Some(PieceType::Pawn) | Some(PieceType::King) | None => Ok(()),
+        }
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    fn from_str(s: &str) -> Result<Move, ParseMoveError> {
+        if !s.is_ascii() || (s.len() != 4 && s.len() != 5) {
+            return Err(ParseMoveError::WrongLength(s.to_string()));
+        }
+        let from = s[0..2].parse()?;
+        let to = s[2..4].parse()?;
+        let promotion = match s[4..].chars().next() {
+            None => None,
+            Some('q') => Some(PieceType::Queen),
+            Some('r') => Some(PieceType::Rook),
+            Some('b') => Some(PieceType::Bishop),
+            Some('n') => Some(PieceType::Knight),
+            Some(c) => return Err(ParseMoveError::UnknownPromotion(c)),
+        };
+        Ok(Move {
+            from,
+            to,
+            promotion,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParseMoveError, ParseSquareError};
+    use crate::board::{Move, PieceType, Square};
+
+    #[test]
+    fn square_names() {
+        assert_eq!(Square::E2.to_string(), "e2");
+        assert_eq!(Square::A1.to_string(), "a1");
+        assert_eq!(Square::H8.to_string(), "h8");
+        assert_eq!("e4".parse(), Ok(Square::E4));
+        assert_eq!("h8".parse(), Ok(Square::H8));
+        for square in Square::all() {
+            assert_eq!(square.to_string().parse(), Ok(square));
+        }
+    }
+
+    #[test]
+    fn square_errors() {
+        assert_eq!(
+            "i4".parse::<Square>(),
+            Err(ParseSquareError::OutOfRange("i4".to_string()))
+        );
+        assert_eq!(
+            "e9".parse::<Square>(),
+            Err(ParseSquareError::OutOfRange("e9".to_string()))
+        );
+        assert_eq!(
+            "e0".parse::<Square>(),
+            Err(ParseSquareError::OutOfRange("e0".to_string()))
+        );
+        assert_eq!(
+            "E4".parse::<Square>(),
+            Err(ParseSquareError::InvalidFormat("E4".to_string()))
+        );
+        assert_eq!(
+            "e44".parse::<Square>(),
+            Err(ParseSquareError::InvalidFormat("e44".to_string()))
+        );
+    }
+
+    #[test]
+    fn uci_moves() {
+        let m: Move = "e2e4".parse().unwrap();
+        assert_eq!(m, Move::new(Square::E2, Square::E4));
+        assert_eq!(m.to_string(), "e2e4");
+
+        let m: Move = "e7e8q".parse().unwrap();
+        assert_eq!(m.promotion, Some(PieceType::Queen));
+        assert_eq!(m.to_string(), "e7e8q");
+        assert_eq!(
+            "a2a1n".parse::<Move>().unwrap().promotion,
+            Some(PieceType::Knight)
+        );
+    }
+
+    #[test]
+    fn uci_move_errors() {
+        assert_eq!(
+            "e2e".parse::<Move>(),
+            Err(ParseMoveError::WrongLength("e2e".to_string()))
+        );
+        assert_eq!(
+            "e2e9".parse::<Move>(),
+            Err(ParseMoveError::InvalidSquare(ParseSquareError::OutOfRange(
+                "e9".to_string()
+            )))
+        );
+        assert_eq!(
+            "e7e8k".parse::<Move>(),
+            Err(ParseMoveError::UnknownPromotion('k'))
+        );
+    }
+
+    #[test]
+    fn every_legal_move_round_trips() {
+        let board: crate::board::Board =
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1"
+                .parse()
+                .unwrap();
+        for m in board.legal_moves() {
+            assert_eq!(m.to_string().parse(), Ok(m));
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt::Display;
+
+use crate::board::{Board, Color, PieceType, Square};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GameOutcome {
+    Checkmate { winner: Color },
+    Stalemate,
+    FiftyMoveRule,
+    ThreefoldRepetition,
+    InsufficientMaterial,
+}
+
+impl Display for GameOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GameOutcome::Checkmate { winner } => write!(f, "Checkmate, {} wins", winner),
+            GameOutcome::Stalemate => write!(f, "Draw by stalemate"),
+            GameOutcome::FiftyMoveRule => write!(f, "Draw by the fifty move rule"),
+            GameOutcome::ThreefoldRepetition => write!(f, "Draw by threefold repetition"),
+            GameOutcome::InsufficientMaterial => write!(f, "Draw by insufficient material"),
+        }
+    }
+}
+
+impl Board {
+    //None while the game is still going
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        if self.legal_moves().is_empty() {
+            if self.is_in_check(self.current_turn()) {
+                return Some(GameOutcome::Checkmate {
+                    winner: self.current_turn().opposite(),
+                });
+            }
+            return Some(GameOutcome::Stalemate);
+        }
+        if self.has_insufficient_material() {
+            return Some(GameOutcome::InsufficientMaterial);
+        }
+        //Fifty moves by each side is a hundred half moves
+        if self.halfmove_clock() >= 100 {
+            return Some(GameOutcome::FiftyMoveRule);
+        }
+        if self.repetition_count() >= 3 {
+            return Some(GameOutcome::ThreefoldRepetition);
+        }
+        None
+    }
+
+    //Neither side can possibly deliver mate: lone kings, a single minor piece,
+    //or bishops that all stand on the same colour of square
+    pub fn has_insufficient_material(&self) -> bool {
+        let mut knights = 0;
+        let mut bishop_square_colors = Vec::new();
+        for square in Square::all() {
+            let Some(piece) = self.get_piece(square) else { continue };
+            match piece.piece_type {
+                PieceType::King => (),
+                PieceType::Knight => knights += 1,
+                PieceType::Bishop => {
+                    bishop_square_colors.push((square.file().index() + square.rank().index()) % 2)
+                }
+                PieceType::Pawn | PieceType::Rook | PieceType::Queen => return false,
+            }
+        }
+        match (knights, bishop_square_colors.len()) {
+            (0, 0) | (1, 0) | (0, 1) => true,
+            (0, _) => bishop_square_colors
+                .iter()
+                .all(|c| *c == bishop_square_colors[0]),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GameOutcome;
+    use crate::board::{Board, Color, Move, Square};
+
+    #[test]
+    fn game_in_progress() {
+        assert_eq!(Board::construct_board().outcome(), None);
+    }
+
+    #[test]
+    fn checkmate() {
+        //Fool's mate
+        let board = Board::board_from_fen_string(
+            "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".to_string(),
+        );
+        assert_eq!(
+            board.outcome(),
+            Some(GameOutcome::Checkmate {
+                winner: Color::Black
+            })
+        );
+    }
+
+    #[test]
+    fn stalemate() {
+        let board = Board::board_from_fen_string("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1".to_string());
+        assert_eq!(board.outcome(), Some(GameOutcome::Stalemate));
+    }
+
+    #[test]
+    fn fifty_move_rule() {
+        let board = Board::board_from_fen_string("4k3/8/8/8/8/8/8/R3K3 w - - 100 80".to_string());
+        assert_eq!(board.outcome(), Some(GameOutcome::FiftyMoveRule));
+
+        let board = Board::board_from_fen_string("4k3/8/8/8/8/8/8/R3K3 w - - 99 80".to_string());
+        assert_eq!(board.outcome(), None);
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        let mut board = Board::construct_board();
+        let shuffle = [
+            Move::new(Square::G1, Square::F3),
+            Move::new(Square::G8, Square::F6),
+            Move::new(Square::F3, Square::G1),
+            Move::new(Square::F6, Square::G8),
+        ];
+        for _ in 0..2 {
+            assert_eq!(board.outcome(), None);
+            for m in shuffle {
+                board.apply_move(m);
+            }
+        }
+        assert_eq!(board.repetition_count(), 3);
+        assert_eq!(board.outcome(), Some(GameOutcome::ThreefoldRepetition));
+    }
+
+    #[test]
+    fn insufficient_material() {
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KB2 w - - 0 1",
+            //Bishops on the same colour of square
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        ] {
+            let board = Board::board_from_fen_string(fen.to_string());
+            assert_eq!(
+                board.outcome(),
+                Some(GameOutcome::InsufficientMaterial),
+                "{}",
+                fen
+            );
+        }
+
+        for fen in [
+            "4k3/8/8/8/8/8/8/3NKN2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1",
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+        ] {
+            let board = Board::board_from_fen_string(fen.to_string());
+            assert_eq!(board.outcome(), None, "{}", fen);
+        }
+    }
+}
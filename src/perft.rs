@@ -0,0 +1,122 @@
+use crate::board::{Board, Move};
+
+impl Board {
+    //Counts the leaf nodes of the legal move tree, the standard way to check a move generator
+    pub fn perft(&self, depth: u32) -> u64 {
+        let mut board = self.clone();
+        perft_nodes(&mut board, depth)
+    }
+
+    //Perft split by the first move, for narrowing down where two generators disagree
+    pub fn divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        let mut board = self.clone();
+        let mut results = Vec::new();
+        if depth == 0 {
+            return results;
+        }
+        for m in self.legal_moves() {
+            let undo = board.apply_move(m);
+            results.push((m, perft_nodes(&mut board, depth - 1)));
+            board.unmake_move(undo);
+        }
+        results
+    }
+}
+
+fn perft_nodes(board: &mut Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = board.legal_moves();
+    //Counting the moves is enough at the last ply
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for m in moves {
+        let undo = board.apply_move(m);
+        nodes += perft_nodes(board, depth - 1);
+        board.unmake_move(undo);
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+
+    //Node counts from https://www.chessprogramming.org/Perft_Results
+    fn check(fen: &str, expected: &[u64]) {
+        let board: Board = fen.parse().unwrap();
+        for (depth, nodes) in expected.iter().enumerate() {
+            assert_eq!(
+                board.perft(depth as u32 + 1),
+                *nodes,
+                "{} depth {}",
+                fen,
+                depth + 1
+            );
+        }
+    }
+
+    #[test]
+    fn start_position() {
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[20, 400, 8902, 197281],
+        );
+    }
+
+    #[test]
+    fn kiwipete() {
+        check(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[48, 2039, 97862],
+        );
+    }
+
+    #[test]
+    fn position_3() {
+        check(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2812, 43238, 674624],
+        );
+    }
+
+    #[test]
+    fn position_4() {
+        check(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9467, 422333],
+        );
+        //The same position with colours reversed
+        check(
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            &[6, 264, 9467, 422333],
+        );
+    }
+
+    #[test]
+    fn position_5() {
+        check(
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1486, 62379],
+        );
+    }
+
+    #[test]
+    fn position_6() {
+        check(
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[46, 2079, 89890],
+        );
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let board = Board::construct_board();
+        let divide = board.divide(3);
+        assert_eq!(divide.len(), 20);
+        assert_eq!(divide.iter().map(|(_, n)| n).sum::<u64>(), board.perft(3));
+    }
+}
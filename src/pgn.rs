@@ -0,0 +1,597 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::board::fen::FenError;
+use crate::board::{Board, Color, Move};
+use crate::san::SanError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnMove {
+    pub san: String,
+    pub m: Move,
+    pub nags: Vec<u8>,
+    //Comments before the move, which only happens at the start of a variation
+    pub comments_before: Vec<String>,
+    //Comments that follow the move
+    pub comments: Vec<String>,
+    //Alternatives to this move, each a line starting from the position before it
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnGame {
+    //In the order they appear in the file
+    pub tags: Vec<(String, String)>,
+    pub start: Board,
+    //Comments before the first move
+    pub comments: Vec<String>,
+    pub moves: Vec<PgnMove>,
+    pub result: String,
+}
+
+impl PgnGame {
+    //Reads every game in the text, stopping at the first error
+    pub fn read_all(text: &str) -> Result<Vec<PgnGame>, PgnError> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+        };
+        let mut games = Vec::new();
+        while !parser.only_comments_left() {
+            games.push(parser.game()?);
+        }
+        Ok(games)
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    //The position at the end of the main line
+    pub fn final_board(&self) -> Board {
+        let mut board = self.start.clone();
+        for m in &self.moves {
+            board.apply_move(m.m);
+        }
+        board
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PgnError {
+    //Something that isn't valid PGN, with the line it was found on
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    InvalidFen(FenError),
+    //Plies count from 1 at the start of the game, so they can be found in any line
+    IllegalMove {
+        ply: usize,
+        move_number: u32,
+        color: Color,
+        san: String,
+        error: SanError,
+    },
+}
+
+impl Display for PgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PgnError::Syntax { line, message } => write!(f, "Line {}: {}", line, message),
+            PgnError::InvalidFen(e) => write!(f, "Invalid FEN tag: {}", e),
+            PgnError::IllegalMove {
+                ply,
+                move_number,
+                color,
+                san,
+                error,
+            } => {
+                let dots = if *color == Color::White { "." } else { "..." };
+                write!(
+                    f,
+                    "Ply {} ({}{} {}): {}",
+                    ply, move_number, dots, san, error
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+//Reads a single game, ignoring anything after it
+impl FromStr for PgnGame {
+    type Err = PgnError;
+
+    fn from_str(s: &str) -> Result<PgnGame, PgnError> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            position: 0,
+        };
+        parser.game()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    StartVariation,
+    EndVariation,
+    San(String),
+    Result(String),
+}
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+fn syntax(line: usize, message: &str) -> PgnError {
+    PgnError::Syntax {
+        line,
+        message: message.to_string(),
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, PgnError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let at_line_start = i == 0 || chars[i - 1] == '\n';
+        match c {
+            '\n' => {
+                line += 1;
+                i += 1;
+            }
+            c if c.is_whitespace() => i += 1,
+            //Escaped lines and rest of line comments
+            '%' | ';' if c == ';' || at_line_start => {
+                let start = i + 1;
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                if c == ';' {
+                    let comment: String = chars[start..i].iter().collect();
+                    tokens.push((line, Token::Comment(comment.trim().to_string())));
+                }
+            }
+            '{' => {
+                let start_line = line;
+                let start = i + 1;
+                while i < chars.len() && chars[i] != '}' {
+                    if chars[i] == '\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+                if i == chars.len() {
+                    return Err(syntax(start_line, "comment is never closed"));
+                }
+                let comment: String = chars[start..i].iter().collect();
+                tokens.push((start_line, Token::Comment(comment.trim().to_string())));
+                i += 1;
+            }
+            '[' => {
+                let end = tag_end(&chars, i).ok_or_else(|| syntax(line, "tag is never closed"))?;
+                let tag: String = chars[i + 1..end].iter().collect();
+                tokens.push((
+                    line,
+                    parse_tag(&tag).ok_or_else(|| syntax(line, "malformed tag"))?,
+                ));
+                i = end + 1;
+            }
+            '(' => {
+                tokens.push((line, Token::StartVariation));
+                i += 1;
+            }
+            ')' => {
+                tokens.push((line, Token::EndVariation));
+                i += 1;
+            }
+            '$' => {
+                let start = i + 1;
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let number: String = chars[start..i].iter().collect();
+                let nag = number.parse().map_err(|_| syntax(line, "malformed NAG"))?;
+                tokens.push((line, Token::Nag(nag)));
+            }
+            _ => {
+                let start = i;
+                while i < chars.len() && !chars[i].is_whitespace() && !"{}()[];$".contains(chars[i])
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                tokenize_word(&word, line, &mut tokens)?;
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+//A word is a result, a move number, a move with optional annotation glyphs, or a
+//move number run straight into its move like 12.Nf3
+fn tokenize_word(
+    word: &str,
+    line: usize,
+    tokens: &mut Vec<(usize, Token)>,
+) -> Result<(), PgnError> {
+    if RESULTS.contains(&word) {
+        tokens.push((line, Token::Result(word.to_string())));
+        return Ok(());
+    }
+    //Castling written with zeros is the only move that starts with a digit
+    let mut word = word;
+    let without_number = word.trim_start_matches(|c: char| c.is_ascii_digit());
+    if !word.starts_with("0-0") && without_number.len() != word.len() {
+        let rest = without_number.trim_start_matches('.');
+        if rest.len() == without_number.len() {
+            return Err(syntax(line, &format!("unexpected '{}'", word)));
+        }
+        if rest.is_empty() {
+            return Ok(());
+        }
+        word = rest;
+    }
+
+    let san = word.trim_end_matches(['!', '?']);
+    if san.is_empty()
+        || !san
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+#=-".contains(c))
+    {
+        return Err(syntax(line, &format!("unexpected '{}'", word)));
+    }
+    tokens.push((line, Token::San(san.to_string())));
+    let nag = match &word[san.len()..] {
+        "" => return Ok(()),
+        "!" => 1,
+        "?" => 2,
+        "!!" => 3,
+        "??" => 4,
+        "!?" => 5,
+        "?!" => 6,
+        _ => return Err(syntax(line, &format!("unknown annotation on '{}'", word))),
+    };
+    tokens.push((line, Token::Nag(nag)));
+    Ok(())
+}
+
+//The closing bracket of the tag opened at `start`, skipping any inside the
+//quoted value. Tags have to fit on one line.
+fn tag_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut quoted = false;
+    let mut i = start + 1;
+    while i < chars.len() && chars[i] != '\n' {
+        match chars[i] {
+            '\\' if quoted => i += 1,
+            '"' => quoted = !quoted,
+            ']' if !quoted => return Some(i),
+            _ => (),
+        }
+        i += 1;
+    }
+    None
+}
+
+fn parse_tag(tag: &str) -> Option<Token> {
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace)?;
+    let name = &tag[..name_end];
+    let value = tag[name_end..].trim();
+    if name.is_empty() || !value.starts_with('"') || !value.ends_with('"') || value.len() < 2 {
+        return None;
+    }
+    let value = value[1..value.len() - 1]
+        .replace("\\\"", "\"")
+        .replace("\\\\", "\\");
+    Some(Token::Tag(name.to_string(), value))
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, t)| t)
+    }
+
+    fn line_number(&self) -> usize {
+        match self.tokens.get(self.position) {
+            Some((line, _)) => *line,
+            None => self.tokens.last().map_or(1, |(line, _)| *line),
+        }
+    }
+
+    //Comments after the last game don't start another one
+    fn only_comments_left(&self) -> bool {
+        self.tokens[self.position..]
+            .iter()
+            .all(|(_, token)| matches!(token, Token::Comment(_)))
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).map(|(_, t)| t.clone());
+        self.position += 1;
+        token
+    }
+
+    fn game(&mut self) -> Result<PgnGame, PgnError> {
+        let mut tags = Vec::new();
+        while let Some(Token::Tag(name, value)) = self.peek() {
+            tags.push((name.clone(), value.clone()));
+            self.next();
+        }
+        let start = match tags.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) => fen.parse().map_err(PgnError::InvalidFen)?,
+            None => Board::construct_board(),
+        };
+
+        let mut comments = Vec::new();
+        while let Some(Token::Comment(comment)) = self.peek() {
+            comments.push(comment.clone());
+            self.next();
+        }
+        let moves = self.moves(start.clone(), 1, false)?;
+        //A game cut off without its termination marker, by the end of the text
+        //or by the next game's tags, is treated as unfinished
+        let result = match self.peek() {
+            Some(Token::Result(result)) => {
+                let result = result.clone();
+                self.next();
+                result
+            }
+            _ => "*".to_string(),
+        };
+        Ok(PgnGame {
+            tags,
+            start,
+            comments,
+            moves,
+            result,
+        })
+    }
+
+    //Reads moves until the end of the line, replaying each one on the board
+    fn moves(
+        &mut self,
+        mut board: Board,
+        first_ply: usize,
+        variation: bool,
+    ) -> Result<Vec<PgnMove>, PgnError> {
+        let mut moves: Vec<PgnMove> = Vec::new();
+        //The position before the last move, which is where its variations start
+        let mut previous = board.clone();
+        //Comments waiting for the first move of a variation
+        let mut comments_before = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some(Token::Result(_)) | Some(Token::Tag(..)) if !variation => {
+                    return Ok(moves)
+                }
+                None | Some(Token::Result(_)) | Some(Token::Tag(..)) => {
+                    return Err(syntax(self.line_number(), "variation is never closed"));
+                }
+                //An empty variation would have nowhere to keep its comments
+                Some(Token::EndVariation) if variation && moves.is_empty() => {
+                    return Err(syntax(self.line_number(), "variation without any moves"));
+                }
+                Some(Token::EndVariation) if variation => {
+                    self.next();
+                    return Ok(moves);
+                }
+                Some(Token::EndVariation) => {
+                    return Err(syntax(self.line_number(), "unexpected ')'"));
+                }
+                _ => (),
+            }
+            match self.next().unwrap() {
+                Token::San(san) => {
+                    let ply = first_ply + moves.len();
+                    let m = board
+                        .parse_san(&san)
+                        .map_err(|error| PgnError::IllegalMove {
+                            ply,
+                            move_number: board.fullmove_number(),
+                            color: board.current_turn(),
+                            san: san.clone(),
+                            error,
+                        })?;
+                    previous = board.clone();
+                    board.apply_move(m);
+                    moves.push(PgnMove {
+                        san,
+                        m,
+                        nags: Vec::new(),
+                        comments_before: std::mem::take(&mut comments_before),
+                        comments: Vec::new(),
+                        variations: Vec::new(),
+                    });
+                }
+                Token::Comment(comment) => match moves.last_mut() {
+                    Some(last) => last.comments.push(comment),
+                    None => comments_before.push(comment),
+                },
+                Token::Nag(nag) => match moves.last_mut() {
+                    Some(last) => last.nags.push(nag),
+                    None => return Err(syntax(self.line_number(), "annotation before any move")),
+                },
+                Token::StartVariation => {
+                    if moves.is_empty() {
+                        return Err(syntax(self.line_number(), "variation before any move"));
+                    }
+                    let ply = first_ply + moves.len() - 1;
+                    let line = self.moves(previous.clone(), ply, true)?;
+                    moves.last_mut().unwrap().variations.push(line);
+                }
+                Token::EndVariation | Token::Result(_) | Token::Tag(..) => unreachable!(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PgnError, PgnGame};
+    use crate::board::{Color, Move, Square};
+    use crate::san::SanError;
+
+    const GAME: &str = r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+[Annotator "Someone \"quoted\" [and bracketed]"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 {This opening is called the Ruy Lopez.}
+4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Nb8 10. d4 Nbd7
+11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 14. Bg5 b4 15. Nb1 h6 16. Bh4 c5 17. dxe5
+Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6 20. Nbd2 Nxd6 21. Nc4 Nxc4 22. Bxc4 Nb6
+23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7 Rxe1+ 26. Qxe1 Kxf7 27. Qe3 Qg5 28. Qxg5
+hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4 cxb4 32. Ra5 Nd5 33. f3 Bc8 34. Kf2 Bf5
+35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4 38. g3 Nxh3 39. Kd2 Kb5 40. Rd6 Kc5 41. Ra6
+Nf2 42. g4 Bd3 43. Re6 1/2-1/2
+"#;
+
+    #[test]
+    fn reads_tags_and_moves() {
+        let games = PgnGame::read_all(GAME).unwrap();
+        assert_eq!(games.len(), 1);
+        let game = &games[0];
+        assert_eq!(game.tag("White"), Some("Fischer, Robert J."));
+        assert_eq!(
+            game.tag("Annotator"),
+            Some("Someone \"quoted\" [and bracketed]")
+        );
+        assert_eq!(game.tag("ECO"), None);
+        assert_eq!(game.tags.len(), 8);
+        assert_eq!(game.moves.len(), 85);
+        assert_eq!(game.result, "1/2-1/2");
+        assert_eq!(
+            game.moves[5].comments,
+            vec!["This opening is called the Ruy Lopez."]
+        );
+        assert_eq!(
+            game.final_board().to_fen_string(),
+            "8/8/4R1p1/2k3p1/1p4P1/1P1b1P2/3K1n2/8 b - - 2 43"
+        );
+    }
+
+    #[test]
+    fn reads_variations_and_annotations() {
+        let text =
+            "1. e4 $1 e5 (1... c5 2. Nf3 (2. c3 {Alapin}) d6) ({French} 1... e6!?) 2. Nf3?! \
+                    ; rest of line comment\n Nc6 *";
+        let game = &PgnGame::read_all(text).unwrap()[0];
+        assert_eq!(game.moves.len(), 4);
+        assert_eq!(game.result, "*");
+        assert_eq!(game.moves[0].nags, vec![1]);
+        assert_eq!(game.moves[2].nags, vec![6]);
+        assert_eq!(game.moves[2].comments, vec!["rest of line comment"]);
+
+        let variations = &game.moves[1].variations;
+        assert_eq!(variations.len(), 2);
+        assert_eq!(variations[0].len(), 3);
+        assert_eq!(variations[0][0].san, "c5");
+        assert_eq!(variations[0][1].variations[0][0].san, "c3");
+        assert_eq!(variations[0][1].variations[0][0].comments, vec!["Alapin"]);
+        assert_eq!(variations[1][0].nags, vec![5]);
+        assert_eq!(variations[1][0].comments_before, vec!["French"]);
+        assert!(variations[1][0].comments.is_empty());
+        assert_eq!(variations[1][0].m, Move::new(Square::E7, Square::E6));
+    }
+
+    #[test]
+    fn reads_several_games_and_fen_starts() {
+        let text = r#"[Event "One"]
+1.e4 e5 1-0
+
+[Event "Two"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30"]
+
+30... Kd7 31. e4 0-1
+"#;
+        let games = PgnGame::read_all(text).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].moves.len(), 2);
+        assert_eq!(games[0].result, "1-0");
+        assert_eq!(games[1].tag("Event"), Some("Two"));
+        assert_eq!(games[1].moves[1].san, "e4");
+        assert_eq!(games[1].result, "0-1");
+
+        let first: PgnGame = text.parse().unwrap();
+        assert_eq!(first, games[0]);
+
+        assert_eq!(PgnGame::read_all("1. e4 * {trailing}").unwrap().len(), 1);
+
+        //The next game's tags end a game that is missing its result
+        let games = PgnGame::read_all("[Event \"A\"]\n1. e4 e5\n\n[Event \"B\"]\n1. d4 *").unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].result, "*");
+        assert_eq!(games[0].moves.len(), 2);
+        assert_eq!(games[1].tag("Event"), Some("B"));
+    }
+
+    #[test]
+    fn reports_illegal_move_ply() {
+        let error =
+            PgnGame::read_all("1. e4 e5 2. Nf3 Nc6 3. Bb5 Ke7 4. Ke2 Ke8 5. Qxd8 *").unwrap_err();
+        assert_eq!(
+            error,
+            PgnError::IllegalMove {
+                ply: 9,
+                move_number: 5,
+                color: Color::White,
+                san: "Qxd8".to_string(),
+                error: SanError::IllegalMove("Qxd8".to_string()),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Ply 9 (5. Qxd8): Qxd8 is not legal in this position"
+        );
+
+        //Inside a variation the ply is counted from the start of the game too
+        let error = PgnGame::read_all("1. e4 e5 (1... d5 2. exd5 Qxd5 3. Nc3 Qe4+ 4. d3) 2. Nf3 *")
+            .unwrap_err();
+        assert!(matches!(error, PgnError::IllegalMove { ply: 7, .. }));
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        assert!(matches!(
+            PgnGame::read_all("1. e4 {unclosed"),
+            Err(PgnError::Syntax { line: 1, .. })
+        ));
+        assert!(matches!(
+            PgnGame::read_all("[Event \"x\"]\n1. e4 (1. d4 *"),
+            Err(PgnError::Syntax { line: 2, .. })
+        ));
+        assert!(matches!(
+            PgnGame::read_all("1. e4 ({just a note}) *"),
+            Err(PgnError::Syntax { line: 1, .. })
+        ));
+        assert!(matches!(
+            PgnGame::read_all("1. e4 ) *"),
+            Err(PgnError::Syntax { line: 1, .. })
+        ));
+        assert!(matches!(
+            PgnGame::read_all("[FEN \"bad\"]\n*"),
+            Err(PgnError::InvalidFen(_))
+        ));
+    }
+}
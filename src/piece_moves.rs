@@ -1,269 +1,141 @@
-use crate::board::{Board, Color, Coord, Move, Piece};
-
-pub fn pawn_moves(board: Board, x: isize, y: isize, piece: Piece) -> Vec<Move> {
-    let mut moves = Vec::new();
-    let starting_rank = match piece.color {
-        Color::White => 1,
-        Color::Black => 6,
-    };
-    let m = Move {
-        from: Coord { x: x, y: y },
-        to: Coord {
-            x: x,
-            y: match piece.color {
-                Color::White => y + 1,
-                Color::Black => y - 1,
-            },
-        },
-    };
-    moves.push(m);
-    if y == starting_rank {
-        let m = Move {
-            from: Coord { x: x, y: y },
-            to: Coord {
-                x: x,
-                y: match piece.color {
-                    Color::White => y + 2,
-                    Color::Black => y - 2,
-                },
-            },
-        };
-        moves.push(m)
-    }
-
-    let right_capture_move = Move {
-        from: Coord { x: x, y: y },
-        to: Coord {
-            x: x + 1,
-            y: match piece.color {
-                Color::White => y + 1,
-                Color::Black => y - 1,
-            },
-        },
-    };
-    if board
-        .get_square(right_capture_move.to.x, right_capture_move.to.y)
-        .piece
-        .is_some()
-    {
-        moves.push(right_capture_move);
-    }
-
-    if x == 0 {
-        return moves;
-    }
-
-    let left_capture_move = Move {
-        from: Coord { x: x, y: y },
-        to: Coord {
-            x: x - 1,
-            y: match piece.color {
-                Color::White => y + 1,
-                Color::Black => y - 1,
-            },
-        },
-    };
-
-    if board
-        .get_square(left_capture_move.to.x, left_capture_move.to.y)
-        .piece
-        .is_some()
-    {
-        moves.push(left_capture_move);
+use crate::bitboard::{self, bit, Bitboard, Squares};
+use crate::board::{Board, Color, File, Move, Piece, PieceType, Rank, Square};
+
+const PROMOTION_PIECES: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+];
+
+//Generators push every pseudo-legal move for the piece on `from` onto `moves`.
+//Moves onto friendly pieces are never generated, but moves that leave the king
+//in check are left for Board::legal_moves to filter out.
+pub fn moves_for_piece(board: &Board, from: Square, piece: Piece, moves: &mut Vec<Move>) {
+    match piece.piece_type {
+        PieceType::Pawn => pawn_moves(board, from, piece.color, moves),
+        PieceType::Knight => knight_moves(board, from, piece.color, moves),
+        PieceType::Bishop => bishop_moves(board, from, piece.color, moves),
+        PieceType::Rook => rook_moves(board, from, piece.color, moves),
+        PieceType::Queen => queen_moves(board, from, piece.color, moves),
+        PieceType::King => {
+            king_moves(board, from, piece.color, moves);
+            castling_moves(board, from, piece.color, moves);
+        }
     }
-
-    moves
 }
 
-pub fn knight_moves(x: isize, y: isize) -> Vec<Move> {
-    let mut moves = Vec::new();
-
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x - 1, y: y - 2 },
-    });
-
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x - 1, y: y + 2 },
-    });
-
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x + 1, y: y + 2 },
-    });
-
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x + 1, y: y - 2 },
-    });
-
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x + 2, y: y + 1 },
-    });
-
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x + 2, y: y - 1 },
-    });
-
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x - 2, y: y - 1 },
-    });
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x - 2, y: y + 1 },
-    });
-
-    moves
+fn push_targets(from: Square, targets: Bitboard, moves: &mut Vec<Move>) {
+    for to in Squares(targets) {
+        moves.push(Move::new(from, to));
+    }
 }
 
-macro_rules! check_capture_or_block {
-    ($board:ident, $m:ident, $dir:ident, $moves:ident) => {
-        if $m.to.x < 0 || $m.to.x > 7 || $m.to.y < 0 || $m.to.y > 7 {
-            $dir = false;
-        } else if $board.get_square($m.to.x, $m.to.y).piece.is_some()
-            && $board.get_square($m.to.x, $m.to.y).piece.unwrap().color
-                != $board.get_square($m.from.x, $m.from.y).piece.unwrap().color
-        {
-            $moves.push($m);
-            $dir = false;
-        } else if $board.get_square($m.to.x, $m.to.y).piece.is_some() {
-            $dir = false;
-        } else {
-            $moves.push($m);
+//A pawn reaching the last rank must promote, so one move becomes four
+fn push_pawn_move(moves: &mut Vec<Move>, from: Square, to: Square) {
+    if matches!(to.rank(), Rank::First | Rank::Eighth) {
+        for piece_type in PROMOTION_PIECES {
+            moves.push(Move {
+                from,
+                to,
+                promotion: Some(piece_type),
+            });
         }
-    };
+    } else {
+        moves.push(Move::new(from, to));
+    }
 }
 
-pub fn bishop_moves(board: Board, x: isize, y: isize) -> Vec<Move> {
-    let mut moves = Vec::new();
-
-    let mut ne = true;
-    let mut nw = true;
-    let mut se = true;
-    let mut sw = true;
-
-    for i in 1..8 {
-        if ne {
-            let m = Move {
-                from: Coord { x: x, y: y },
-                to: Coord { x: x + i, y: y + i },
-            };
-            check_capture_or_block!(board, m, ne, moves);
-        }
-        if nw {
-            let m = Move {
-                from: Coord { x: x, y: y },
-                to: Coord { x: x - i, y: y + i },
-            };
-            check_capture_or_block!(board, m, nw, moves);
-        }
-        if se {
-            let m = Move {
-                from: Coord { x: x, y: y },
-                to: Coord { x: x + i, y: y - i },
-            };
-            check_capture_or_block!(board, m, se, moves);
-        }
-        if sw {
-            let m = Move {
-                from: Coord { x: x, y: y },
-                to: Coord { x: x - i, y: y - i },
-            };
-            check_capture_or_block!(board, m, sw, moves);
+pub fn pawn_moves(board: &Board, from: Square, color: Color, moves: &mut Vec<Move>) {
+    let (starting_rank, en_passant_rank, forward) = match color {
+        Color::White => (Rank::Second, Rank::Sixth, 1),
+        Color::Black => (Rank::Seventh, Rank::Third, -1),
+    };
+    //Pawns on the last rank have nowhere to go
+    let Some(one) = from.offset(0, forward) else { return };
+
+    //Pushes are only possible onto empty squares
+    let empty = !board.occupied();
+    if empty & bit(one) != 0 {
+        push_pawn_move(moves, from, one);
+        if from.rank() == starting_rank {
+            if let Some(two) = one.offset(0, forward).filter(|two| empty & bit(*two) != 0) {
+                push_pawn_move(moves, from, two);
+            }
         }
     }
-    moves
-}
-
-pub fn rook_moves(board: Board, x: isize, y: isize) -> Vec<Move> {
-    let mut moves = Vec::new();
-    let mut n = true;
-    let mut s = true;
-    let mut e = true;
-    let mut w = true;
 
-    for i in 1..8 {
-        if n {
-            let m = Move {
-                from: Coord { x: x, y: y },
-                to: Coord { x: x, y: y + i },
-            };
-            check_capture_or_block!(board, m, n, moves);
-        }
-        if s {
-            let m = Move {
-                from: Coord { x: x, y: y },
-                to: Coord { x: x, y: y - i },
-            };
-            check_capture_or_block!(board, m, s, moves);
-        }
-        if e {
-            let m = Move {
-                from: Coord { x: x, y: y },
-                to: Coord { x: x + i, y: y },
-            };
-            check_capture_or_block!(board, m, e, moves);
-        }
-        if w {
-            let m = Move {
-                from: Coord { x: x, y: y },
-                to: Coord { x: x - i, y: y },
-            };
-            check_capture_or_block!(board, m, w, moves);
+    //Captures are only possible onto squares holding an enemy piece or en
+    //passant, onto an empty target behind an enemy pawn beside this one
+    let mut capturable = board.occupied_by(color.opposite());
+    if let Some(target) = board.en_passant_target() {
+        let beside = Square::from_file_rank(target.file(), from.rank());
+        if target.rank() == en_passant_rank
+            && empty & bit(target) != 0
+            && board.get_piece(beside) == Some(Piece::new(color.opposite(), PieceType::Pawn))
+        {
+            capturable |= bit(target);
         }
     }
-
-    moves
+    for to in Squares(bitboard::pawn_attacks(from, color) & capturable) {
+        push_pawn_move(moves, from, to);
+    }
 }
 
-pub fn queen_moves(board: Board, x: isize, y: isize) -> Vec<Move> {
-    let mut moves = Vec::new();
+pub fn knight_moves(board: &Board, from: Square, color: Color, moves: &mut Vec<Move>) {
+    let targets = bitboard::knight_attacks(from) & !board.occupied_by(color);
+    push_targets(from, targets, moves);
+}
 
-    moves.append(&mut rook_moves(board, x, y));
-    moves.append(&mut bishop_moves(board, x, y));
+pub fn bishop_moves(board: &Board, from: Square, color: Color, moves: &mut Vec<Move>) {
+    let targets = bitboard::bishop_attacks(from, board.occupied()) & !board.occupied_by(color);
+    push_targets(from, targets, moves);
+}
 
-    moves
+pub fn rook_moves(board: &Board, from: Square, color: Color, moves: &mut Vec<Move>) {
+    let targets = bitboard::rook_attacks(from, board.occupied()) & !board.occupied_by(color);
+    push_targets(from, targets, moves);
 }
 
-pub fn king_moves(x: isize, y: isize) -> Vec<Move> {
-    let mut moves = Vec::new();
+pub fn queen_moves(board: &Board, from: Square, color: Color, moves: &mut Vec<Move>) {
+    let targets = bitboard::queen_attacks(from, board.occupied()) & !board.occupied_by(color);
+    push_targets(from, targets, moves);
+}
 
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x + 1, y: y },
-    });
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x - 1, y: y },
-    });
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x, y: y + 1 },
-    });
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x, y: y - 1 },
-    });
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x + 1, y: y + 1 },
-    });
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x - 1, y: y + 1 },
-    });
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x + 1, y: y - 1 },
-    });
-    moves.push(Move {
-        from: Coord { x: x, y: y },
-        to: Coord { x: x - 1, y: y - 1 },
-    });
+pub fn king_moves(board: &Board, from: Square, color: Color, moves: &mut Vec<Move>) {
+    let targets = bitboard::king_attacks(from) & !board.occupied_by(color);
+    push_targets(from, targets, moves);
+}
 
-    moves
+//Castling is represented as the king moving two squares towards the rook.
+//Whether the king passes through check is left to the legal move filter.
+pub fn castling_moves(board: &Board, from: Square, color: Color, moves: &mut Vec<Move>) {
+    let home_rank = match color {
+        Color::White => Rank::First,
+        Color::Black => Rank::Eighth,
+    };
+    if from != Square::from_file_rank(File::E, home_rank) {
+        return;
+    }
+    let on_home_rank = |file| Square::from_file_rank(file, home_rank);
+    let rooks = board.pieces(PieceType::Rook, color);
+    let occupied = board.occupied();
+    let rights = board.castling_rights();
+
+    //f and g files must be empty for kingside, b, c and d for queenside
+    let kingside_path = bit(on_home_rank(File::F)) | bit(on_home_rank(File::G));
+    let queenside_path =
+        bit(on_home_rank(File::B)) | bit(on_home_rank(File::C)) | bit(on_home_rank(File::D));
+    if rights.kingside(color)
+        && occupied & kingside_path == 0
+        && rooks & bit(on_home_rank(File::H)) != 0
+    {
+        moves.push(Move::new(from, on_home_rank(File::G)));
+    }
+    if rights.queenside(color)
+        && occupied & queenside_path == 0
+        && rooks & bit(on_home_rank(File::A)) != 0
+    {
+        moves.push(Move::new(from, on_home_rank(File::C)));
+    }
 }
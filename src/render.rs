@@ -0,0 +1,310 @@
+use std::fmt::{self, Display};
+
+use crate::bitboard::{bit, Bitboard};
+use crate::board::{Board, Color, File, Move, Piece, PieceType, Rank, Square};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum RenderStyle {
+    //Piece letters, upper case for White, which works everywhere
+    #[default]
+    Ascii,
+    //Chess glyphs, for terminals and fonts that have them
+    Unicode,
+    //Chess glyphs on coloured squares using 256-colour escape codes
+    Ansi,
+}
+
+//How to draw a board. Highlights are usually the legal destinations of a
+//piece. In colour they change the square's background; the plain text
+//styles widen every square to bracket highlights as [x] and the last move
+//as (x), and stay one character a square when there is nothing to mark.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RenderOptions {
+    pub style: RenderStyle,
+    //Rank numbers down the side and file letters along the bottom
+    pub labels: bool,
+    //Black at the bottom
+    pub flipped: bool,
+    pub last_move: Option<Move>,
+    pub highlights: Bitboard,
+}
+
+//Background colours for light and dark squares
+const SQUARE_COLORS: (u8, u8) = (180, 137);
+const LAST_MOVE_COLORS: (u8, u8) = (186, 143);
+const HIGHLIGHT_COLORS: (u8, u8) = (152, 109);
+//Foreground colours for the pieces
+const WHITE_PIECE: u8 = 231;
+const BLACK_PIECE: u8 = 16;
+const RESET: &str = "\x1b[0m";
+
+//A board drawn with some options, written out through Display
+pub struct Diagram<'a> {
+    board: &'a Board,
+    options: &'a RenderOptions,
+}
+
+impl Board {
+    pub fn render<'a>(&'a self, options: &'a RenderOptions) -> Diagram<'a> {
+        Diagram {
+            board: self,
+            options,
+        }
+    }
+}
+
+impl Display for Diagram<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let options = self.options;
+        writeln!(f, "{} to move", self.board.current_turn())?;
+        //White's side is drawn at the bottom unless the board is flipped
+        let mut ranks = Rank::ALL;
+        let mut files = File::ALL;
+        if options.flipped {
+            files.reverse();
+        } else {
+            ranks.reverse();
+        }
+
+        for rank in ranks {
+            if options.labels {
+                write!(f, "{} ", rank)?;
+            }
+            for file in files {
+                self.write_square(f, Square::from_file_rank(file, rank))?;
+            }
+            if options.style == RenderStyle::Ansi {
+                write!(f, "{}", RESET)?;
+            }
+            writeln!(f)?;
+        }
+
+        if options.labels {
+            write!(f, "  ")?;
+            for file in files {
+                if self.wide() {
+                    write!(f, " {} ", file)?;
+                } else {
+                    write!(f, "{}", file)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Diagram<'_> {
+    //Whether squares are three characters wide, which colour always needs
+    //and the plain styles need for room to mark squares
+    fn wide(&self) -> bool {
+        self.options.style == RenderStyle::Ansi
+            || self.options.last_move.is_some()
+            || self.options.highlights != 0
+    }
+
+    fn write_square(&self, f: &mut fmt::Formatter, square: Square) -> fmt::Result {
+        let piece = self.board.get_piece(square);
+        let highlighted = self.options.highlights & bit(square) != 0;
+        let last_move = self
+            .options
+            .last_move
+            .is_some_and(|m| m.from == square || m.to == square);
+        let symbol = match (self.options.style, piece) {
+            (RenderStyle::Ascii, Some(piece)) => letter(piece),
+            (RenderStyle::Ascii, None) => '-',
+            (RenderStyle::Unicode, Some(piece)) => glyph(piece),
+            (RenderStyle::Unicode, None) => '·',
+            (RenderStyle::Ansi, _) => {
+                return self.write_coloured_square(f, square, highlighted, last_move)
+            }
+        };
+        if !self.wide() {
+            return write!(f, "{}", symbol);
+        }
+        let (open, close) = if highlighted {
+            ('[', ']')
+        } else if last_move {
+            ('(', ')')
+        } else {
+            (' ', ' ')
+        };
+        write!(f, "{}{}{}", open, symbol, close)
+    }
+
+    fn write_coloured_square(
+        &self,
+        f: &mut fmt::Formatter,
+        square: Square,
+        highlighted: bool,
+        last_move: bool,
+    ) -> fmt::Result {
+        let (light, dark) = if highlighted {
+            HIGHLIGHT_COLORS
+        } else if last_move {
+            LAST_MOVE_COLORS
+        } else {
+            SQUARE_COLORS
+        };
+        let is_light = (square.file().index() + square.rank().index()) % 2 == 1;
+        let background = if is_light { light } else { dark };
+        write!(f, "\x1b[48;5;{}m", background)?;
+        match self.board.get_piece(square) {
+            //The filled glyphs are used for both sides and told apart by colour
+            Some(piece) => {
+                let foreground = match piece.color {
+                    Color::White => WHITE_PIECE,
+                    Color::Black => BLACK_PIECE,
+                };
+                let filled = Piece::new(Color::Black, piece.piece_type);
+                write!(f, "\x1b[38;5;{}m {} ", foreground, glyph(filled))
+            }
+            None => write!(f, "   "),
+        }
+    }
+}
+
+fn letter(piece: Piece) -> char {
+    let letter = match piece.piece_type {
+        PieceType::Pawn => 'P',
+        PieceType::Knight => 'N',
+        PieceType::Bishop => 'B',
+        PieceType::Rook => 'R',
+        PieceType::Queen => 'Q',
+        PieceType::King => 'K',
+    };
+    match piece.color {
+        Color::White => letter,
+        Color::Black => letter.to_ascii_lowercase(),
+    }
+}
+
+fn glyph(piece: Piece) -> char {
+    match (piece.color, piece.piece_type) {
+        (Color::White, PieceType::Pawn) => '♙',
+        (Color::White, PieceType::Knight) => '♘',
+        (Color::White, PieceType::Bishop) => '♗',
+        (Color::White, PieceType::Rook) => '♖',
+        (Color::White, PieceType::Queen) => '♕',
+        (Color::White, PieceType::King) => '♔',
+        (Color::Black, PieceType::Pawn) => '♟',
+        (Color::Black, PieceType::Knight) => '♞',
+        (Color::Black, PieceType::Bishop) => '♝',
+        (Color::Black, PieceType::Rook) => '♜',
+        (Color::Black, PieceType::Queen) => '♛',
+        (Color::Black, PieceType::King) => '♚',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RenderOptions, RenderStyle, RESET};
+    use crate::bitboard::bit;
+    use crate::board::{Board, Move, Square};
+
+    fn after_e4() -> Board {
+        let mut board = Board::construct_board();
+        board.make_move("e2e4".parse().unwrap()).unwrap();
+        board
+    }
+
+    #[test]
+    fn plain_diagram_matches_display() {
+        let board = after_e4();
+        let expected = concat!(
+            "Black to move\n",
+            "rnbqkbnr\npppppppp\n--------\n--------\n",
+            "----P---\n--------\nPPPP-PPP\nRNBQKBNR\n"
+        );
+        assert_eq!(
+            board.render(&RenderOptions::default()).to_string(),
+            expected
+        );
+        assert_eq!(board.to_string(), expected);
+    }
+
+    #[test]
+    fn labels_and_flipping() {
+        let board = after_e4();
+        let options = RenderOptions {
+            labels: true,
+            ..RenderOptions::default()
+        };
+        let diagram = board.render(&options).to_string();
+        assert!(diagram.starts_with("Black to move\n8 rnbqkbnr\n"));
+        assert!(diagram.ends_with("4 ----P---\n3 --------\n2 PPPP-PPP\n1 RNBQKBNR\n  abcdefgh\n"));
+
+        let options = RenderOptions {
+            flipped: true,
+            ..options
+        };
+        let diagram = board.render(&options).to_string();
+        assert!(diagram.starts_with("Black to move\n1 RNBKQBNR\n2 PPP-PPPP\n"));
+        assert!(diagram.ends_with("8 rnbkqbnr\n  hgfedcba\n"));
+    }
+
+    #[test]
+    fn unicode_glyphs_and_marks() {
+        let board = Board::construct_board();
+        let options = RenderOptions {
+            style: RenderStyle::Unicode,
+            ..RenderOptions::default()
+        };
+        let diagram = board.render(&options).to_string();
+        assert!(diagram.contains("♜♞♝♛♚♝♞♜\n♟♟♟♟♟♟♟♟\n········\n"));
+
+        //Marked squares are bracketed, occupied ones included
+        let options = RenderOptions {
+            highlights: bit(Square::F3) | bit(Square::H3) | bit(Square::G1),
+            ..options
+        };
+        let diagram = board.render(&options).to_string();
+        assert!(diagram.ends_with(concat!(
+            " ·  ·  ·  ·  · [·] · [·]\n",
+            " ♙  ♙  ♙  ♙  ♙  ♙  ♙  ♙ \n",
+            " ♖  ♘  ♗  ♕  ♔  ♗ [♘] ♖ \n"
+        )));
+    }
+
+    #[test]
+    fn plain_last_move() {
+        let options = RenderOptions {
+            labels: true,
+            last_move: Some(Move::new(Square::E2, Square::E4)),
+            ..RenderOptions::default()
+        };
+        let diagram = after_e4().render(&options).to_string();
+        assert!(diagram.contains("\n4  -  -  -  - (P) -  -  - \n"));
+        assert!(diagram.ends_with(
+            "2  P  P  P  P (-) P  P  P \n1  R  N  B  Q  K  B  N  R \n   a  b  c  d  e  f  g  h \n"
+        ));
+    }
+
+    #[test]
+    fn ansi_colours() {
+        let board = after_e4();
+        let options = RenderOptions {
+            style: RenderStyle::Ansi,
+            labels: true,
+            last_move: Some(Move {
+                from: Square::E2,
+                to: Square::E4,
+                promotion: None,
+            }),
+            highlights: bit(Square::E5),
+            ..RenderOptions::default()
+        };
+        let diagram = board.render(&options).to_string();
+        let lines: Vec<&str> = diagram.lines().collect();
+        assert_eq!(lines.len(), 10);
+        for line in &lines[1..9] {
+            assert!(line.ends_with(RESET));
+            assert_eq!(line.matches("\x1b[48;5;").count(), 8);
+        }
+        //e4 is light and was just moved to, e5 is dark and highlighted, e3 is a plain dark square
+        assert!(lines[5].contains("\x1b[48;5;186m\x1b[38;5;231m ♟ "));
+        assert!(lines[4].contains("\x1b[48;5;109m   "));
+        assert!(lines[6].contains("\x1b[48;5;137m   "));
+        assert_eq!(lines[9], "   a  b  c  d  e  f  g  h ");
+    }
+}
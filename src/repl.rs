@@ -0,0 +1,200 @@
+use std::io::{self, BufRead, Write};
+
+use crate::bitboard::bit;
+use crate::board::{Board, Color, Move, Square};
+use crate::game::Game;
+use crate::render::{RenderOptions, RenderStyle};
+
+const HELP: &str = "Enter moves in SAN (Nf3, O-O, e8=Q) or UCI (g1f3, e7e8q).
+Commands:
+  undo    take back the last move
+  fen     print the position as FEN
+  flip    turn the board around
+  style   draw the board as ascii, unicode or color
+  show    mark where the piece on a square can go, as in 'show g1'
+  moves   list the legal moves
+  pgn     print the game so far as PGN
+  resign  resign the game for the side to move
+  help    show this message
+  quit    leave without finishing the game";
+
+//Plays a game on the terminal, reading moves and commands a line at a time
+//until the game ends or the input runs out
+pub fn run(board: Board, input: impl BufRead, output: &mut impl Write) -> io::Result<()> {
+    let mut game = Game::from_board(board);
+    let mut options = RenderOptions {
+        labels: true,
+        ..RenderOptions::default()
+    };
+    writeln!(output, "Type 'help' for a list of commands")?;
+    print_board(output, &game, &options)?;
+    let mut lines = input.lines();
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+        let Some(line) = lines.next() else { return Ok(()) };
+        let line = line?;
+        //Commands that take an argument, like 'style unicode'
+        let (command, argument) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        match line.trim() {
+            "" => (),
+            "help" => writeln!(output, "{}", HELP)?,
+            "quit" | "exit" => return Ok(()),
+            "fen" => writeln!(output, "{}", game.board().to_fen_string())?,
+            "pgn" => write!(output, "{}", game)?,
+            "flip" => {
+                options.flipped = !options.flipped;
+                print_board(output, &game, &options)?;
+            }
+            _ if command == "style" => {
+                options.style = match argument.trim() {
+                    "ascii" => RenderStyle::Ascii,
+                    "unicode" => RenderStyle::Unicode,
+                    "color" => RenderStyle::Ansi,
+                    _ => {
+                        writeln!(output, "The styles are ascii, unicode and color")?;
+                        continue;
+                    }
+                };
+                print_board(output, &game, &options)?;
+            }
+            _ if command == "show" => {
+                let Ok(square) = argument.trim().parse::<Square>() else {
+                    writeln!(output, "Give the square of a piece, as in 'show g1'")?;
+                    continue;
+                };
+                let highlights = game
+                    .board()
+                    .legal_moves()
+                    .iter()
+                    .filter(|m| m.from == square)
+                    .fold(0, |targets, m| targets | bit(m.to));
+                let options = RenderOptions {
+                    highlights,
+                    ..options.clone()
+                };
+                print_board(output, &game, &options)?;
+            }
+            "moves" => {
+                let board = game.board();
+                let moves: Vec<String> = board
+                    .legal_moves()
+                    .iter()
+                    .filter_map(|m| m.to_san(board).ok())
+                    .collect();
+                writeln!(output, "{}", moves.join(" "))?;
+            }
+            "undo" => match game.undo() {
+                Some(_) => print_board(output, &game, &options)?,
+                None => writeln!(output, "There are no moves to take back")?,
+            },
+            "resign" => {
+                let loser = game.board().current_turn();
+                game.set_tag("Result", if loser == Color::White { "0-1" } else { "1-0" });
+                writeln!(output, "{} resigns. {}", loser, game.result())?;
+                return Ok(());
+            }
+            text => {
+                let m = match parse_move(game.board(), text) {
+                    Ok(m) => m,
+                    Err(message) => {
+                        writeln!(output, "{}", message)?;
+                        continue;
+                    }
+                };
+                game.play(m).expect("parsed moves are legal");
+                print_board(output, &game, &options)?;
+                if let Some(outcome) = game.board().outcome() {
+                    writeln!(output, "{}. {}", outcome, game.result())?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+//UCI is tried first since no UCI move is also valid SAN
+fn parse_move(board: &Board, text: &str) -> Result<Move, String> {
+    if let Ok(m) = text.parse::<Move>() {
+        if board.legal_moves().contains(&m) {
+            return Ok(m);
+        }
+        return Err(format!("{} is not legal in this position", m));
+    }
+    board.parse_san(text).map_err(|e| e.to_string())
+}
+
+//Draws the current position with the last move marked
+fn print_board(output: &mut impl Write, game: &Game, options: &RenderOptions) -> io::Result<()> {
+    let options = RenderOptions {
+        last_move: game.moves().last().copied(),
+        ..options.clone()
+    };
+    write!(output, "{}", game.board().render(&options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run;
+    use crate::board::Board;
+
+    fn session(board: Board, input: &str) -> String {
+        let mut output = Vec::new();
+        run(board, input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn plays_san_and_uci_moves() {
+        let output = session(Board::construct_board(), "e4\ne7e5\nfen\n");
+        assert!(output.contains("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"));
+    }
+
+    #[test]
+    fn rejects_illegal_moves() {
+        let output = session(Board::construct_board(), "e5\ne2e5\nNf3 x\n");
+        assert!(output.contains("e5 is not legal in this position"));
+        assert!(output.contains("e2e5 is not legal in this position"));
+        assert!(output.contains("'Nf3 x' is not a valid move"));
+    }
+
+    #[test]
+    fn undo_flip_and_moves() {
+        let output = session(Board::construct_board(), "Nf3\nundo\nundo\nmoves\nflip\n");
+        assert!(output.contains("There are no moves to take back"));
+        assert!(output
+            .contains("\n> Na3 Nc3 Nf3 Nh3 a3 a4 b3 b4 c3 c4 d3 d4 e3 e4 f3 f4 g3 g4 h3 h4\n"));
+        //Flipped, black's pieces are at the bottom read from h to a
+        assert!(output.ends_with(concat!(
+            "1 RNBKQBNR\n2 PPPPPPPP\n3 --------\n4 --------\n",
+            "5 --------\n6 --------\n7 pppppppp\n8 rnbkqbnr\n  hgfedcba\n> "
+        )));
+    }
+
+    #[test]
+    fn styles_and_highlights() {
+        let output = session(
+            Board::construct_board(),
+            "show g1\nstyle unicode\nstyle x\ne4\n",
+        );
+        assert!(output.contains("3  -  -  -  -  - [-] - [-]\n2  P  P  P  P  P  P  P  P \n"));
+        //The highlights only last for the one diagram
+        assert!(output.contains("1 ♖♘♗♕♔♗♘♖\n"));
+        assert!(output.contains("4  ·  ·  ·  · (♙) ·  ·  · \n"));
+        assert!(output.contains("The styles are ascii, unicode and color"));
+
+        let output = session(Board::construct_board(), "show\nshow e4\nstyle color\n");
+        assert_eq!(output.matches("Give the square of a piece").count(), 1);
+        assert!(output.contains("\x1b[48;5;"));
+    }
+
+    #[test]
+    fn game_ends_on_mate_or_resignation() {
+        let output = session(Board::construct_board(), "f3\ne5\ng4\nQh4\nfen\n");
+        assert!(output.contains("Checkmate, Black wins. 0-1"));
+        assert!(!output.contains("KQkq"));
+
+        let output = session(Board::construct_board(), "e4\nresign\ne5\n");
+        assert!(output.ends_with("Black resigns. 1-0\n"));
+    }
+}
@@ -0,0 +1,376 @@
+use std::fmt::Display;
+
+use crate::board::{Board, File, Move, PieceType, Rank, Square};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SanError {
+    Empty,
+    InvalidSyntax(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+}
+
+impl Display for SanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SanError::Empty => write!(f, "No move given"),
+            SanError::InvalidSyntax(san) => write!(f, "'{}' is not a valid move", san),
+            SanError::IllegalMove(san) => write!(f, "{} is not legal in this position", san),
+            SanError::AmbiguousMove(san) => {
+                write!(f, "{} could mean more than one move", san)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+fn piece_letter(piece_type: PieceType) -> &'static str {
+    match piece_type {
+        PieceType::Pawn => "",
+        PieceType::Knight => "N",
+        PieceType::Bishop => "B",
+        PieceType::Rook => "R",
+        PieceType::Queen => "Q",
+        PieceType::King => "K",
+    }
+}
+
+fn piece_from_letter(c: char) -> Option<PieceType> {
+    match c {
+        'N' => Some(PieceType::Knight),
+        'B' => Some(PieceType::Bishop),
+        'R' => Some(PieceType::Rook),
+        'Q' => Some(PieceType::Queen),
+        'K' => Some(PieceType::King),
+        _ => None,
+    }
+}
+
+impl Move {
+    //Fails for moves that aren't legal on the board, which SAN can't describe
+    pub fn to_san(self, board: &Board) -> Result<String, SanError> {
+        let illegal = || SanError::IllegalMove(self.to_string());
+        let Some(piece) = board.get_piece(self.from) else { return Err(illegal()) };
+        if !board.legal_moves().contains(&self) {
+            return Err(illegal());
+        }
+        let (from_file, to_file) = (self.from.file(), self.to.file());
+        let mut san = String::new();
+
+        if piece.piece_type == PieceType::King && from_file.index().abs_diff(to_file.index()) == 2 {
+            san.push_str(if to_file > from_file { "O-O" } else { "O-O-O" });
+        } else {
+            let capture = board.get_piece(self.to).is_some()
+                || (piece.piece_type == PieceType::Pawn && from_file != to_file);
+            san.push_str(piece_letter(piece.piece_type));
+
+            if piece.piece_type == PieceType::Pawn {
+                if capture {
+                    san.push(from_file.to_char());
+                }
+            } else {
+                //Name just enough of the origin square to tell apart the other pieces
+                //of the same kind that could also reach the destination
+                let rivals: Vec<Move> = board
+                    .legal_moves()
+                    .into_iter()
+                    .filter(|m| {
+                        m.to == self.to
+                            && m.from != self.from
+                            && board.get_piece(m.from) == Some(piece)
+                    })
+                    .collect();
+                if !rivals.is_empty() {
+                    if rivals.iter().all(|m| m.from.file() != from_file) {
+                        san.push(from_file.to_char());
+                    } else if rivals.iter().all(|m| m.from.rank() != self.from.rank()) {
+                        san.push(self.from.rank().to_char());
+                    } else {
+                        san.push_str(&self.from.to_string());
+                    }
+                }
+            }
+
+            if capture {
+                san.push('x');
+            }
+            san.push_str(&self.to.to_string());
+            if let Some(promotion) = self.promotion {
+                san.push('=');
+                san.push_str(piece_letter(promotion));
+            }
+        }
+
+        let mut after = board.clone();
+        after.apply_move(self);
+        if after.is_in_check(after.current_turn()) {
+            san.push(if after.legal_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+        Ok(san)
+    }
+}
+
+impl Board {
+    //Accepts the usual variations: check and annotation suffixes are optional,
+    //castling may use zeros and the '=' before a promotion piece may be left out
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let trimmed = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        if trimmed.is_empty() {
+            return Err(SanError::Empty);
+        }
+        let invalid = || SanError::InvalidSyntax(san.to_string());
+
+        let candidates: Vec<Move> = match trimmed {
+            "O-O" | "0-0" | "O-O-O" | "0-0-0" => {
+                let kingside = trimmed.len() == 3;
+                self.legal_moves()
+                    .into_iter()
+                    .filter(|m| {
+                        let piece = self.get_piece(m.from).unwrap();
+                        let to_file = if kingside { File::G } else { File::C };
+                        piece.piece_type == PieceType::King
+                            && m.from.file() == File::E
+                            && m.to.file() == to_file
+                    })
+                    .collect()
+            }
+            _ => {
+                let mut rest: Vec<char> = trimmed.chars().collect();
+                let piece_type = match piece_from_letter(rest[0]) {
+                    Some(piece_type) => {
+                        rest.remove(0);
+                        piece_type
+                    }
+                    None => PieceType::Pawn,
+                };
+
+                let mut promotion = None;
+                if let Some(p) = rest.last().and_then(|c| piece_from_letter(*c)) {
+                    promotion = Some(p);
+                    rest.pop();
+                    if rest.last() == Some(&'=') {
+                        rest.pop();
+                    }
+                }
+
+                if rest.len() < 2 {
+                    return Err(invalid());
+                }
+                let destination: String = rest[rest.len() - 2..].iter().collect();
+                let to: Square = destination.parse().map_err(|_| invalid())?;
+                rest.truncate(rest.len() - 2);
+                if rest.last() == Some(&'x') {
+                    rest.pop();
+                }
+
+                //Whatever is left narrows down the origin square
+                let mut from_file = None;
+                let mut from_rank = None;
+                for c in rest {
+                    match c {
+                        'a'..='h' if from_file.is_none() => from_file = File::from_char(c),
+                        '1'..='8' if from_rank.is_none() => from_rank = Rank::from_char(c),
+                        _ => return Err(invalid()),
+                    }
+                }
+
+                self.legal_moves()
+                    .into_iter()
+                    .filter(|m| {
+                        let piece = self.get_piece(m.from).unwrap();
+                        piece.piece_type == piece_type
+                            && m.to == to
+                            && m.promotion == promotion
+                            && from_file.is_none_or(|file| m.from.file() == file)
+                            && from_rank.is_none_or(|rank| m.from.rank() == rank)
+                    })
+                    .collect()
+            }
+        };
+
+        match candidates.len() {
+            0 => Err(SanError::IllegalMove(san.to_string())),
+            1 => Ok(candidates[0]),
+            _ => Err(SanError::AmbiguousMove(san.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SanError;
+    use crate::board::{Board, Move, PieceType, Square};
+
+    #[test]
+    fn formats_moves() {
+        let board = Board::construct_board();
+        assert_eq!(
+            Move::new(Square::E2, Square::E4).to_san(&board).unwrap(),
+            "e4"
+        );
+        assert_eq!(
+            Move::new(Square::G1, Square::F3).to_san(&board).unwrap(),
+            "Nf3"
+        );
+
+        let board: Board = "r3k2r/8/8/3p4/4P3/8/8/R3K2R w KQkq - 0 1".parse().unwrap();
+        assert_eq!(
+            Move::new(Square::E4, Square::D5).to_san(&board).unwrap(),
+            "exd5"
+        );
+        assert_eq!(
+            Move::new(Square::E1, Square::G1).to_san(&board).unwrap(),
+            "O-O"
+        );
+        assert_eq!(
+            Move::new(Square::E1, Square::C1).to_san(&board).unwrap(),
+            "O-O-O"
+        );
+        assert_eq!(
+            Move::new(Square::A1, Square::A8).to_san(&board).unwrap(),
+            "Rxa8+"
+        );
+
+        //En passant is a capture even though the target square is empty
+        let board: Board = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2".parse().unwrap();
+        assert_eq!(
+            Move::new(Square::E5, Square::D6).to_san(&board).unwrap(),
+            "exd6"
+        );
+    }
+
+    #[test]
+    fn formats_disambiguation() {
+        //Knights on b8 and f6 can both reach d7
+        let board: Board = "1n2k3/8/5n2/8/8/8/8/4K3 b - - 0 1".parse().unwrap();
+        assert_eq!(
+            Move::new(Square::B8, Square::D7).to_san(&board).unwrap(),
+            "Nbd7"
+        );
+        //Rooks on a1 and a5 share a file
+        let board: Board = "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1".parse().unwrap();
+        assert_eq!(
+            Move::new(Square::A1, Square::A3).to_san(&board).unwrap(),
+            "R1a3"
+        );
+        //Three queens need the full square
+        let board: Board = "7k/8/8/8/Q1Q5/8/Q7/4K3 w - - 0 1".parse().unwrap();
+        assert_eq!(
+            Move::new(Square::A4, Square::B3).to_san(&board).unwrap(),
+            "Qa4b3"
+        );
+        //Pinned pieces don't count as rivals
+        let board: Board = "4k3/8/8/3b4/8/5N2/8/1N5K w - - 0 1".parse().unwrap();
+        assert_eq!(
+            Move::new(Square::B1, Square::D2).to_san(&board).unwrap(),
+            "Nd2"
+        );
+    }
+
+    #[test]
+    fn formats_promotion_and_mate() {
+        let board: Board = "7k/5P2/6K1/8/8/8/8/8 w - - 0 1".parse().unwrap();
+        let promotion = Move {
+            from: Square::F7,
+            to: Square::F8,
+            promotion: Some(PieceType::Queen),
+        };
+        assert_eq!(promotion.to_san(&board).unwrap(), "f8=Q#");
+
+        let board: Board = "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1".parse().unwrap();
+        let promotion = Move {
+            from: Square::B7,
+            to: Square::B8,
+            promotion: Some(PieceType::Rook),
+        };
+        assert_eq!(promotion.to_san(&board).unwrap(), "b8=R+");
+    }
+
+    #[test]
+    fn parses_moves() {
+        let board: Board = "r3k2r/1n6/8/3p4/4P3/2n5/8/R3K2R b KQkq - 0 1"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            board.parse_san("Nbd6"),
+            Ok(Move::new(Square::B7, Square::D6))
+        );
+        assert_eq!(
+            board.parse_san("dxe4"),
+            Ok(Move::new(Square::D5, Square::E4))
+        );
+        assert_eq!(
+            board.parse_san("O-O-O"),
+            Ok(Move::new(Square::E8, Square::C8))
+        );
+        assert_eq!(
+            board.parse_san("0-0"),
+            Ok(Move::new(Square::E8, Square::G8))
+        );
+        assert_eq!(
+            board.parse_san("Nxe4"),
+            Ok(Move::new(Square::C3, Square::E4))
+        );
+        assert_eq!(
+            board.parse_san("Kd7!?"),
+            Ok(Move::new(Square::E8, Square::D7))
+        );
+
+        let board: Board = "3k4/4P3/3K4/8/8/8/8/8 w - - 0 1".parse().unwrap();
+        let queen = Move {
+            from: Square::E7,
+            to: Square::E8,
+            promotion: Some(PieceType::Queen),
+        };
+        assert_eq!(board.parse_san("e8=Q+"), Ok(queen));
+        assert_eq!(board.parse_san("e8Q"), Ok(queen));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let board: Board = "1n2k3/8/5n2/8/8/8/8/4K3 b - - 0 1".parse().unwrap();
+        assert_eq!(board.parse_san(""), Err(SanError::Empty));
+        assert_eq!(
+            board.parse_san("Nd7"),
+            Err(SanError::AmbiguousMove("Nd7".to_string()))
+        );
+        assert_eq!(
+            board.parse_san("Nh1"),
+            Err(SanError::IllegalMove("Nh1".to_string()))
+        );
+        assert_eq!(
+            board.parse_san("Zz9"),
+            Err(SanError::InvalidSyntax("Zz9".to_string()))
+        );
+        assert_eq!(
+            board.parse_san("O-O"),
+            Err(SanError::IllegalMove("O-O".to_string()))
+        );
+        //A promotion has to name its piece
+        let board: Board = "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1".parse().unwrap();
+        assert_eq!(
+            board.parse_san("b8"),
+            Err(SanError::IllegalMove("b8".to_string()))
+        );
+    }
+
+    #[test]
+    fn round_trips_every_legal_move() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            let board: Board = fen.parse().unwrap();
+            for legal in board.legal_moves() {
+                let san = legal.to_san(&board).unwrap();
+                assert_eq!(board.parse_san(&san), Ok(legal), "{} in {}", san, fen);
+            }
+        }
+    }
+}
@@ -0,0 +1,460 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::board::{Board, Move, PieceType};
+use crate::eval::{evaluate, piece_value};
+use crate::tt::{Bound, TranspositionTable};
+
+//Scores are in centipawns from the side to move's point of view. Mate scores
+//count down from MATE by the number of plies it takes to deliver it
+pub const MATE: i32 = 30000;
+pub const MAX_DEPTH: u32 = 64;
+const INFINITY: i32 = MATE + 1;
+
+//How often the clock and stop flag are looked at, in nodes
+const CHECK_INTERVAL: u64 = 1024;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub movetime: Option<Duration>,
+    pub nodes: Option<u64>,
+    //Only a stop request ends an infinite search
+    pub infinite: bool,
+}
+
+//Reported after every completed iteration, and returned for the last one
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchInfo {
+    pub depth: u32,
+    pub score: i32,
+    pub nodes: u64,
+    pub time: Duration,
+    //Per mille of the transposition table used by this search
+    pub hashfull: u32,
+    //The line both sides are expected to play, starting with the best move
+    pub pv: Vec<Move>,
+}
+
+impl SearchInfo {
+    pub fn best_move(&self) -> Move {
+        self.pv[0]
+    }
+}
+
+//Plies to mate when the score is a mate score, negative when being mated
+pub fn mate_in(score: i32) -> Option<i32> {
+    if score.abs() < MATE - MAX_DEPTH as i32 {
+        return None;
+    }
+    let plies = MATE - score.abs();
+    Some(score.signum() * (plies + 1) / 2)
+}
+
+//Mate scores are stored relative to the position they were found in rather
+//than the root, so they stay right when the position is reached another way
+fn score_to_table(score: i32, ply: u32) -> i32 {
+    match mate_in(score) {
+        Some(_) => score + score.signum() * ply as i32,
+        None => score,
+    }
+}
+
+fn score_from_table(score: i32, ply: u32) -> i32 {
+    match mate_in(score) {
+        Some(_) => score - score.signum() * ply as i32,
+        None => score,
+    }
+}
+
+struct Searcher<'a> {
+    board: Board,
+    limits: &'a SearchLimits,
+    stop: &'a AtomicBool,
+    tt: &'a mut TranspositionTable,
+    start: Instant,
+    nodes: u64,
+    aborted: bool,
+    //The principal variation of the previous iteration, searched first
+    previous_pv: Vec<Move>,
+}
+
+//Searches one ply deeper at a time until a limit is hit or the stop flag is
+//raised, returning the result of the deepest search that finished. Only
+//positions without legal moves have no result.
+pub fn search(
+    board: &Board,
+    limits: &SearchLimits,
+    stop: &AtomicBool,
+    tt: &mut TranspositionTable,
+    mut report: impl FnMut(&SearchInfo),
+) -> Option<SearchInfo> {
+    let moves = board.legal_moves();
+    //Something has to be played even if the first iteration is cut short
+    let mut result = SearchInfo {
+        depth: 0,
+        score: 0,
+        nodes: 0,
+        time: Duration::ZERO,
+        hashfull: 0,
+        pv: vec![*moves.first()?],
+    };
+    tt.new_search();
+    let mut searcher = Searcher {
+        board: board.clone(),
+        limits,
+        stop,
+        tt,
+        start: Instant::now(),
+        nodes: 0,
+        aborted: false,
+        previous_pv: Vec::new(),
+    };
+    let max_depth = limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+    for depth in 1..=max_depth {
+        let mut pv = Vec::new();
+        let score = searcher.negamax(depth, 0, -INFINITY, INFINITY, &mut pv);
+        if searcher.aborted || pv.is_empty() {
+            break;
+        }
+        result = SearchInfo {
+            depth,
+            score,
+            nodes: searcher.nodes,
+            time: searcher.start.elapsed(),
+            hashfull: searcher.tt.hashfull(),
+            pv: pv.clone(),
+        };
+        report(&result);
+        searcher.previous_pv = pv;
+        //No point looking deeper once a forced mate has been found
+        if mate_in(score).is_some() && !limits.infinite {
+            break;
+        }
+    }
+    result.nodes = searcher.nodes;
+    result.time = searcher.start.elapsed();
+    result.hashfull = searcher.tt.hashfull();
+    Some(result)
+}
+
+impl Board {
+    //Searches without any way of being stopped early, so a limit should be set.
+    //Nothing is remembered between calls.
+    pub fn search(&self, limits: &SearchLimits) -> Option<SearchInfo> {
+        let mut tt = TranspositionTable::new(1);
+        search(self, limits, &AtomicBool::new(false), &mut tt, |_| ())
+    }
+}
+
+impl Searcher<'_> {
+    fn negamax(
+        &mut self,
+        depth: u32,
+        ply: u32,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        pv.clear();
+        if self.count_node() {
+            return 0;
+        }
+        if ply > 0 && (self.board.halfmove_clock() >= 100 || self.board.repetition_count() >= 2) {
+            return 0;
+        }
+
+        let in_check = self.board.is_in_check(self.board.current_turn());
+        let mut moves = self.board.legal_moves();
+        if moves.is_empty() {
+            return if in_check { -MATE + ply as i32 } else { 0 };
+        }
+        //Checks are searched a ply deeper so that forced sequences aren't cut off
+        let depth = if in_check { depth + 1 } else { depth };
+        if depth == 0 || ply >= MAX_DEPTH {
+            return self.quiescence(alpha, beta, ply);
+        }
+
+        //The root always searches so that it has a principal variation to return
+        let key = self.board.hash();
+        let entry = self.tt.probe(key);
+        if let Some(entry) = entry.filter(|e| ply > 0 && e.depth >= depth) {
+            let score = score_from_table(entry.score, ply);
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if usable {
+                return score;
+            }
+        }
+
+        let original_alpha = alpha;
+        let pv_move = self.previous_pv.get(ply as usize).copied();
+        let first_move = pv_move.or(entry.and_then(|e| e.best_move));
+        self.order_moves(&mut moves, first_move);
+        let mut child_pv = Vec::new();
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        for m in moves {
+            let undo = self.board.apply_move(m);
+            let score = -self.negamax(depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+            self.board.unmake_move(undo);
+            if self.aborted {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                best_move = Some(m);
+            }
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(m);
+                pv.extend_from_slice(&child_pv);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        //Only follow the previous principal variation while still on it
+        if pv_move.is_some() && pv.first() != pv_move.as_ref() {
+            self.previous_pv.truncate(ply as usize);
+        }
+        let bound = if best >= beta {
+            Bound::Lower
+        } else if best > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.tt
+            .store(key, depth, score_to_table(best, ply), bound, best_move);
+        best
+    }
+
+    //Only captures and promotions are searched so that the position is quiet
+    //before it is evaluated. The side to move can always decline to capture.
+    fn quiescence(&mut self, mut alpha: i32, beta: i32, ply: u32) -> i32 {
+        if self.count_node() {
+            return 0;
+        }
+        let stand_pat = evaluate(&self.board);
+        if stand_pat >= beta || ply >= MAX_DEPTH {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut moves: Vec<Move> = self
+            .board
+            .legal_moves()
+            .into_iter()
+            .filter(|m| m.promotion.is_some() || captured_piece(&self.board, *m).is_some())
+            .collect();
+        self.order_moves(&mut moves, None);
+        for m in moves {
+            let undo = self.board.apply_move(m);
+            let score = -self.quiescence(-beta, -alpha, ply + 1);
+            self.board.unmake_move(undo);
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    //The previous best move first, then captures of the most valuable piece by
+    //the least valuable attacker, then promotions, then everything else.
+    //Middlegame material values are good enough to rank them.
+    fn order_moves(&self, moves: &mut [Move], pv_move: Option<Move>) {
+        moves.sort_by_cached_key(|m| {
+            if Some(*m) == pv_move {
+                return i32::MIN;
+            }
+            let mut key = 0;
+            if let Some(victim) = captured_piece(&self.board, *m) {
+                let attacker = self.board.get_piece(m.from).unwrap();
+                key -= 10 * piece_value(victim).middlegame
+                    - piece_value(attacker.piece_type).middlegame / 10;
+            }
+            if let Some(promotion) = m.promotion {
+                key -= piece_value(promotion).middlegame;
+            }
+            key
+        });
+    }
+
+    //Counts the node and checks the limits, true if the search has to stop
+    fn count_node(&mut self) -> bool {
+        self.nodes += 1;
+        if self.limits.nodes.is_some_and(|nodes| self.nodes > nodes) {
+            self.aborted = true;
+        }
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) && self.out_of_time() {
+            self.aborted = true;
+        }
+        self.aborted
+    }
+
+    fn out_of_time(&self) -> bool {
+        if self.stop.load(Ordering::Relaxed) {
+            return true;
+        }
+        !self.limits.infinite
+            && self
+                .limits
+                .movetime
+                .is_some_and(|movetime| self.start.elapsed() >= movetime)
+    }
+}
+
+//The piece a move takes, including a pawn taken en passant
+fn captured_piece(board: &Board, m: Move) -> Option<PieceType> {
+    if let Some(piece) = board.get_piece(m.to) {
+        return Some(piece.piece_type);
+    }
+    let moved = board.get_piece(m.from)?;
+    if moved.piece_type == PieceType::Pawn && m.from.file() != m.to.file() {
+        return Some(PieceType::Pawn);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mate_in, score_from_table, score_to_table, search, SearchLimits, MATE};
+    use crate::board::Board;
+    use crate::tt::TranspositionTable;
+    use std::sync::atomic::AtomicBool;
+    use std::time::Duration;
+
+    fn depth(depth: u32) -> SearchLimits {
+        SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        }
+    }
+
+    fn best_move(fen: &str, limit: u32) -> String {
+        let board: Board = fen.parse().unwrap();
+        board.search(&depth(limit)).unwrap().best_move().to_string()
+    }
+
+    #[test]
+    fn finds_mate_in_one() {
+        assert_eq!(best_move("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 2), "a1a8");
+    }
+
+    #[test]
+    fn finds_mate_in_two() {
+        //1. Rd8+ Rxd8 2. Rxd8# with either rook
+        let board: Board = "2r4k/6pp/8/8/8/8/3R2PP/3R2K1 w - - 0 1".parse().unwrap();
+        let result = board.search(&depth(4)).unwrap();
+        assert_eq!(mate_in(result.score), Some(2));
+        let pv: Vec<String> = result.pv.iter().map(|m| m.to_string()).collect();
+        assert_eq!(pv.len(), 3);
+        assert_eq!(pv[1], "c8d8");
+    }
+
+    #[test]
+    fn wins_material() {
+        //The queen on d5 is hanging
+        assert_eq!(best_move("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", 1), "d1d5");
+    }
+
+    #[test]
+    fn quiescence_sees_recaptures() {
+        //Taking the pawn on d5 with the queen loses her to the pawn on e6
+        let board: Board = "4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1".parse().unwrap();
+        let result = board.search(&depth(1)).unwrap();
+        assert_ne!(result.best_move().to_string(), "d1d5");
+    }
+
+    #[test]
+    fn principal_variation_is_legal() {
+        let board: Board = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+            .parse()
+            .unwrap();
+        let result = board.search(&depth(4)).unwrap();
+        assert_eq!(result.depth, 4);
+        let mut board = board;
+        for m in result.pv {
+            assert!(board.make_move(m).is_ok());
+        }
+    }
+
+    #[test]
+    fn reports_every_depth() {
+        let mut depths = Vec::new();
+        let mut tt = TranspositionTable::new(1);
+        let board = Board::construct_board();
+        search(
+            &board,
+            &depth(3),
+            &AtomicBool::new(false),
+            &mut tt,
+            |info| depths.push(info.depth),
+        );
+        assert_eq!(depths, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn stops_when_asked_or_out_of_limits() {
+        let board = Board::construct_board();
+        let stop = AtomicBool::new(true);
+        let mut tt = TranspositionTable::new(1);
+        assert!(search(&board, &SearchLimits::default(), &stop, &mut tt, |_| ()).is_some());
+
+        let limits = SearchLimits {
+            movetime: Some(Duration::from_millis(50)),
+            ..SearchLimits::default()
+        };
+        assert!(board.search(&limits).is_some());
+
+        let limits = SearchLimits {
+            nodes: Some(5000),
+            ..SearchLimits::default()
+        };
+        let result = board.search(&limits).unwrap();
+        assert!(result.nodes <= 5001);
+    }
+
+    #[test]
+    fn no_move_without_legal_moves() {
+        let board: Board = "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1".parse().unwrap();
+        assert_eq!(board.search(&depth(3)), None);
+    }
+
+    #[test]
+    fn table_is_reused_between_searches() {
+        let board: Board = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+            .parse()
+            .unwrap();
+        let stop = AtomicBool::new(false);
+        let mut tt = TranspositionTable::new(1);
+        let first = search(&board, &depth(4), &stop, &mut tt, |_| ()).unwrap();
+        assert!(first.hashfull > 0);
+        assert!(tt.probe(board.hash()).is_some());
+        let second = search(&board, &depth(4), &stop, &mut tt, |_| ()).unwrap();
+        assert!(second.nodes < first.nodes);
+        assert_eq!(second.score, first.score);
+    }
+
+    #[test]
+    fn mate_scores() {
+        assert_eq!(mate_in(MATE - 1), Some(1));
+        assert_eq!(mate_in(MATE - 3), Some(2));
+        assert_eq!(mate_in(-MATE + 2), Some(-1));
+        assert_eq!(mate_in(250), None);
+        //Mate in 3 plies from a node 5 plies from the root
+        assert_eq!(score_to_table(MATE - 8, 5), MATE - 3);
+        assert_eq!(score_from_table(MATE - 3, 5), MATE - 8);
+        assert_eq!(score_to_table(-MATE + 8, 5), -MATE + 3);
+        assert_eq!(score_to_table(120, 5), 120);
+    }
+}
@@ -0,0 +1,191 @@
+use std::mem::size_of;
+
+use crate::board::Move;
+
+//How the stored score relates to the real one, which depends on whether the
+//search that produced it failed high, failed low or came back inside its window
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Bound {
+    Exact,
+    //The score is at least this much
+    Lower,
+    //The score is at most this much
+    Upper,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Entry {
+    //The full key, as many positions share a slot
+    pub key: u64,
+    pub depth: u32,
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<Move>,
+    //The search that stored the entry, so old entries can be replaced first
+    pub age: u8,
+}
+
+pub const DEFAULT_MEGABYTES: usize = 16;
+pub const MAX_MEGABYTES: usize = 1024;
+
+//A fixed number of slots indexed by the low bits of the position's hash
+#[derive(Clone, Debug)]
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+    age: u8,
+}
+
+impl TranspositionTable {
+    //Uses as many slots as fit in the given number of megabytes, rounded down
+    //to a power of two
+    pub fn new(megabytes: usize) -> TranspositionTable {
+        let bytes = megabytes.clamp(1, MAX_MEGABYTES) * 1024 * 1024;
+        let slots = (bytes / size_of::<Option<Entry>>()).max(1);
+        let slots = 1 << slots.ilog2();
+        TranspositionTable {
+            entries: vec![None; slots],
+            age: 0,
+        }
+    }
+
+    pub fn resize(&mut self, megabytes: usize) {
+        *self = TranspositionTable::new(megabytes);
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+        self.age = 0;
+    }
+
+    //Called at the start of every search so entries from earlier ones age
+    pub fn new_search(&mut self) {
+        self.age = self.age.wrapping_add(1);
+    }
+
+    fn index(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        self.entries[self.index(key)].filter(|entry| entry.key == key)
+    }
+
+    //An entry is kept over the new one only if it is from the current search,
+    //for a different position and was searched deeper
+    pub fn store(
+        &mut self,
+        key: u64,
+        depth: u32,
+        score: i32,
+        bound: Bound,
+        best_move: Option<Move>,
+    ) {
+        let index = self.index(key);
+        let age = self.age;
+        if let Some(old) = self.entries[index] {
+            if old.age == age && old.key != key && old.depth > depth {
+                return;
+            }
+        }
+        //A shallower search of the same position may not have found a move
+        let best_move = best_move.or_else(|| {
+            self.entries[index]
+                .filter(|old| old.key == key)
+                .and_then(|old| old.best_move)
+        });
+        self.entries[index] = Some(Entry {
+            key,
+            depth,
+            score,
+            bound,
+            best_move,
+            age,
+        });
+    }
+
+    //How full the table is in parts per thousand, as UCI reports it. Only
+    //entries from the current search count and only a sample is looked at.
+    pub fn hashfull(&self) -> u32 {
+        let sample = self.capacity().min(1000);
+        let used = self.entries[..sample]
+            .iter()
+            .filter(|entry| entry.is_some_and(|e| e.age == self.age))
+            .count();
+        (used * 1000 / sample) as u32
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> TranspositionTable {
+        TranspositionTable::new(DEFAULT_MEGABYTES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bound, TranspositionTable};
+    use crate::board::{Move, Square};
+
+    #[test]
+    fn sized_by_memory() {
+        let small = TranspositionTable::new(1);
+        let large = TranspositionTable::new(4);
+        assert!(small.capacity().is_power_of_two());
+        assert_eq!(large.capacity(), small.capacity() * 4);
+    }
+
+    #[test]
+    fn stores_and_probes() {
+        let mut tt = TranspositionTable::new(1);
+        let m = Move::new(Square::E2, Square::E4);
+        assert_eq!(tt.probe(42), None);
+        tt.store(42, 3, 25, Bound::Exact, Some(m));
+        let entry = tt.probe(42).unwrap();
+        assert_eq!(
+            (entry.depth, entry.score, entry.bound),
+            (3, 25, Bound::Exact)
+        );
+        assert_eq!(entry.best_move, Some(m));
+        //Same slot, different position
+        assert_eq!(tt.probe(42 + tt.capacity() as u64), None);
+
+        //A later store without a move keeps the one already known
+        tt.store(42, 4, 30, Bound::Lower, None);
+        assert_eq!(tt.probe(42).unwrap().best_move, Some(m));
+
+        tt.clear();
+        assert_eq!(tt.probe(42), None);
+    }
+
+    #[test]
+    fn replacement_prefers_depth_then_age() {
+        let mut tt = TranspositionTable::new(1);
+        let other = 7 + tt.capacity() as u64;
+        tt.store(7, 8, 0, Bound::Exact, None);
+        //Shallower entries for other positions don't push out deeper ones
+        tt.store(other, 2, 0, Bound::Exact, None);
+        assert!(tt.probe(7).is_some());
+        assert_eq!(tt.probe(other), None);
+        //But anything left over from an earlier search can go
+        tt.new_search();
+        tt.store(other, 2, 0, Bound::Exact, None);
+        assert_eq!(tt.probe(7), None);
+        assert!(tt.probe(other).is_some());
+    }
+
+    #[test]
+    fn hashfull_counts_current_entries() {
+        let mut tt = TranspositionTable::new(1);
+        assert_eq!(tt.hashfull(), 0);
+        for key in 0..500 {
+            tt.store(key, 1, 0, Bound::Exact, None);
+        }
+        assert_eq!(tt.hashfull(), 500);
+        tt.new_search();
+        assert_eq!(tt.hashfull(), 0);
+    }
+}
@@ -0,0 +1,430 @@
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::board::{Board, Color, Move};
+use crate::search::{self, SearchInfo, SearchLimits};
+use crate::tt::{self, TranspositionTable};
+
+const ENGINE_NAME: &str = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+const ENGINE_AUTHOR: &str = "the chess contributors";
+
+//Kept back from the clock so that a move always arrives before the flag falls
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+//Used when the GUI doesn't say how many moves are left until the next time control
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+//Clock information from a go command, which is turned into a movetime
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Clock {
+    time: [Option<Duration>; 2],
+    increment: [Duration; 2],
+    moves_to_go: Option<u32>,
+}
+
+struct Search {
+    stop: Arc<AtomicBool>,
+    infinite: bool,
+    handle: JoinHandle<()>,
+}
+
+struct Engine<W: Write + Send + 'static> {
+    board: Board,
+    output: Arc<Mutex<W>>,
+    search: Option<Search>,
+    //Shared with the search thread, which holds it while searching
+    tt: Arc<Mutex<TranspositionTable>>,
+}
+
+//Speaks UCI over the given streams until quit is received or the input ends.
+//Searches run on their own thread so that stop and isready are answered while
+//they are going.
+pub fn run<W: Write + Send + 'static>(input: impl BufRead, output: W) -> io::Result<()> {
+    let mut engine = Engine {
+        board: Board::construct_board(),
+        output: Arc::new(Mutex::new(output)),
+        search: None,
+        tt: Arc::new(Mutex::new(TranspositionTable::default())),
+    };
+    for line in input.lines() {
+        let line = line?;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((command, args)) = tokens.split_first() else { continue };
+        match *command {
+            "uci" => {
+                engine.send(&format!("id name {}", ENGINE_NAME))?;
+                engine.send(&format!("id author {}", ENGINE_AUTHOR))?;
+                engine.send(&format!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    tt::DEFAULT_MEGABYTES,
+                    tt::MAX_MEGABYTES
+                ))?;
+                engine.send("option name Clear Hash type button")?;
+                engine.send("uciok")?;
+            }
+            "isready" => engine.send("readyok")?,
+            "ucinewgame" => {
+                engine.stop_search();
+                engine.board = Board::construct_board();
+                engine.tt.lock().unwrap().clear();
+            }
+            "position" => {
+                engine.stop_search();
+                engine.position(args)?;
+            }
+            "go" => engine.go(args),
+            "stop" => engine.stop_search(),
+            "setoption" => engine.set_option(args)?,
+            "quit" => {
+                engine.stop_search();
+                return Ok(());
+            }
+            //Unknown commands are ignored, as the protocol asks
+            _ => (),
+        }
+    }
+    //When a script runs out, its last search is allowed to finish
+    engine.finish_search();
+    Ok(())
+}
+
+impl<W: Write + Send + 'static> Engine<W> {
+    fn send(&self, line: &str) -> io::Result<()> {
+        send(&self.output, line)
+    }
+
+    //position [startpos | fen <fen>] [moves <move>...]
+    fn position(&mut self, args: &[&str]) -> io::Result<()> {
+        let moves_at = args
+            .iter()
+            .position(|a| *a == "moves")
+            .unwrap_or(args.len());
+        let board = match args.first() {
+            Some(&"startpos") => Ok(Board::construct_board()),
+            Some(&"fen") => args[1..moves_at]
+                .join(" ")
+                .parse()
+                .map_err(|e| format!("{}", e)),
+            _ => Err("expected startpos or fen".to_string()),
+        };
+        let mut board = match board {
+            Ok(board) => board,
+            Err(e) => return self.send(&format!("info string invalid position: {}", e)),
+        };
+        for text in args.iter().skip(moves_at + 1) {
+            let m = text.parse::<Move>().map_err(|e| e.to_string());
+            if let Err(e) = m.and_then(|m| board.make_move(m).map_err(|e| e.to_string())) {
+                self.send(&format!("info string invalid move {}: {}", text, e))?;
+                break;
+            }
+        }
+        self.board = board;
+        Ok(())
+    }
+
+    fn go(&mut self, args: &[&str]) {
+        self.stop_search();
+        let limits = parse_go(args, self.board.current_turn());
+        let limits_infinite = limits.infinite;
+        let stop = Arc::new(AtomicBool::new(false));
+        let board = self.board.clone();
+        let output = Arc::clone(&self.output);
+        let thread_stop = Arc::clone(&stop);
+        let tt = Arc::clone(&self.tt);
+        let handle = thread::spawn(move || {
+            let mut tt = tt.lock().unwrap();
+            let result = search::search(&board, &limits, &thread_stop, &mut tt, |info| {
+                let _ = send(&output, &info_line(info));
+            });
+            //An infinite search has to wait to be stopped before answering
+            while limits.infinite && !thread_stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(1));
+            }
+            let best_move = match result {
+                Some(result) => result.best_move().to_string(),
+                None => "0000".to_string(),
+            };
+            let _ = send(&output, &format!("bestmove {}", best_move));
+        });
+        self.search = Some(Search {
+            stop,
+            infinite: limits_infinite,
+            handle,
+        });
+    }
+
+    //Waits for the search to send its bestmove, so nothing else is printed
+    //in between
+    fn stop_search(&mut self) {
+        let Some(search) = self.search.take() else { return };
+        search.stop.store(true, Ordering::Relaxed);
+        let _ = search.handle.join();
+    }
+
+    fn finish_search(&mut self) {
+        let Some(search) = self.search.take() else { return };
+        if search.infinite {
+            search.stop.store(true, Ordering::Relaxed);
+        }
+        let _ = search.handle.join();
+    }
+
+    //setoption name <name> [value <value>]
+    fn set_option(&mut self, args: &[&str]) -> io::Result<()> {
+        let value_at = args
+            .iter()
+            .position(|a| *a == "value")
+            .unwrap_or(args.len());
+        let name = args.get(1..value_at).unwrap_or_default().join(" ");
+        let value = args.get(value_at + 1..).unwrap_or_default().join(" ");
+        //Option names are case insensitive
+        match name.to_lowercase().as_str() {
+            "hash" => match value.parse::<usize>() {
+                Ok(megabytes) if (1..=tt::MAX_MEGABYTES).contains(&megabytes) => {
+                    self.stop_search();
+                    self.tt.lock().unwrap().resize(megabytes);
+                    Ok(())
+                }
+                _ => self.send(&format!("info string invalid Hash value {}", value)),
+            },
+            "clear hash" => {
+                self.stop_search();
+                self.tt.lock().unwrap().clear();
+                Ok(())
+            }
+            _ => self.send(&format!("info string unknown option {}", name)),
+        }
+    }
+}
+
+fn send<W: Write>(output: &Mutex<W>, line: &str) -> io::Result<()> {
+    let mut output = output.lock().unwrap();
+    writeln!(output, "{}", line)?;
+    output.flush()
+}
+
+fn info_line(info: &SearchInfo) -> String {
+    let score = match search::mate_in(info.score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", info.score),
+    };
+    let millis = info.time.as_millis().max(1);
+    let pv: Vec<String> = info.pv.iter().map(|m| m.to_string()).collect();
+    format!(
+        "info depth {} score {} nodes {} nps {} hashfull {} time {} pv {}",
+        info.depth,
+        score,
+        info.nodes,
+        info.nodes as u128 * 1000 / millis,
+        info.hashfull,
+        info.time.as_millis(),
+        pv.join(" ")
+    )
+}
+
+//The go keywords followed by a number
+const NUMBER_KEYWORDS: [&str; 8] = [
+    "depth",
+    "movetime",
+    "nodes",
+    "wtime",
+    "btime",
+    "winc",
+    "binc",
+    "movestogo",
+];
+
+//go [depth <n>] [nodes <n>] [movetime <ms>] [wtime <ms>] [btime <ms>] [winc <ms>] [binc <ms>]
+//   [movestogo <n>] [infinite]
+fn parse_go(args: &[&str], side: Color) -> SearchLimits {
+    let mut limits = SearchLimits::default();
+    let mut clock = Clock::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if *arg == "infinite" {
+            limits.infinite = true;
+            continue;
+        }
+        //ponder and searchmoves with its moves take no number, so they are
+        //passed over a word at a time
+        if !NUMBER_KEYWORDS.contains(arg) {
+            continue;
+        }
+        let Some(value) = args.next().and_then(|v| v.parse::<u64>().ok()) else { continue };
+        let millis = Duration::from_millis(value);
+        match *arg {
+            "depth" => limits.depth = Some(value as u32),
+            "movetime" => limits.movetime = Some(millis),
+            "nodes" => limits.nodes = Some(value),
+            "wtime" => clock.time[Color::White as usize] = Some(millis),
+            "btime" => clock.time[Color::Black as usize] = Some(millis),
+            "winc" => clock.increment[Color::White as usize] = millis,
+            "binc" => clock.increment[Color::Black as usize] = millis,
+            "movestogo" => clock.moves_to_go = Some(value as u32),
+            _ => (),
+        }
+    }
+    if limits.movetime.is_none() {
+        limits.movetime = clock.allocate(side);
+    }
+    //A bare go has nothing to stop it but a stop command
+    if limits.depth.is_none() && limits.movetime.is_none() && limits.nodes.is_none() {
+        limits.infinite = true;
+    }
+    limits
+}
+
+impl Clock {
+    //An even share of the remaining time plus most of the increment
+    fn allocate(&self, side: Color) -> Option<Duration> {
+        let time = self.time[side as usize]?;
+        let moves_to_go = self.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let share = time / moves_to_go + self.increment[side as usize] * 3 / 4;
+        let available = time.saturating_sub(MOVE_OVERHEAD);
+        Some(share.min(available).max(Duration::from_millis(1)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_go, run};
+    use crate::board::Color;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    //Lets the test read what the engine wrote from its other thread
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn session(input: &str) -> Vec<String> {
+        let buffer = SharedBuffer::default();
+        run(input.as_bytes(), buffer.clone()).unwrap();
+        let output = buffer.0.lock().unwrap().clone();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| l.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn handshake() {
+        let output = session("uci\nisready\nquit\n");
+        assert!(output[0].starts_with("id name"));
+        assert!(output[1].starts_with("id author"));
+        assert_eq!(
+            output[2..],
+            [
+                "option name Hash type spin default 16 min 1 max 1024",
+                "option name Clear Hash type button",
+                "uciok",
+                "readyok"
+            ]
+        );
+    }
+
+    #[test]
+    fn go_depth_reports_info_and_bestmove() {
+        let output = session("position startpos moves e2e4 e7e5\ngo depth 2\n");
+        assert!(output[0].starts_with("info depth 1 score cp "));
+        assert!(output[1].starts_with("info depth 2 "));
+        assert!(output[2].starts_with("bestmove "));
+        assert_eq!(output.len(), 3);
+    }
+
+    #[test]
+    fn finds_mate_from_fen() {
+        let output = session("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo depth 3\n");
+        assert!(output.iter().any(|l| l.contains("score mate 1")));
+        assert_eq!(output.last().unwrap(), "bestmove a1a8");
+    }
+
+    #[test]
+    fn infinite_search_waits_for_stop() {
+        let output = session("position startpos\ngo infinite\nisready\nstop\nquit\n");
+        assert_eq!(
+            output.iter().filter(|l| l.starts_with("bestmove")).count(),
+            1
+        );
+        assert!(output.iter().any(|l| l == "readyok"));
+        assert!(output.last().unwrap().starts_with("bestmove"));
+    }
+
+    #[test]
+    fn bad_input_is_reported() {
+        let output = session(
+            "position startpos moves e2e4 e2e4\nposition fen bad\nsetoption name Foo value 1\n",
+        );
+        assert!(output[0].starts_with("info string invalid move e2e4"));
+        assert!(output[1].starts_with("info string invalid position"));
+        assert_eq!(output[2], "info string unknown option Foo");
+    }
+
+    #[test]
+    fn hash_options() {
+        let output = session(
+            "setoption name Hash value 1\nsetoption name hash value x\n\
+             setoption name Clear Hash\nucinewgame\nposition startpos\ngo depth 2\n",
+        );
+        assert_eq!(output[0], "info string invalid Hash value x");
+        assert!(output[1].contains(" hashfull "));
+        assert_eq!(output.len(), 4);
+    }
+
+    #[test]
+    fn moves_are_applied_to_the_position() {
+        //After 1. f3 e5 2. g4 black mates, so no move is left for white
+        let output = session("position startpos moves f2f3 e7e5 g2g4 d8h4\ngo depth 1\n");
+        assert_eq!(output, ["bestmove 0000"]);
+    }
+
+    #[test]
+    fn clock_is_turned_into_a_movetime() {
+        let limits = parse_go(
+            &["wtime", "60000", "btime", "1000", "winc", "400"],
+            Color::White,
+        );
+        assert_eq!(limits.movetime, Some(Duration::from_millis(2300)));
+        let limits = parse_go(
+            &["wtime", "60000", "btime", "1000", "movestogo", "1"],
+            Color::Black,
+        );
+        assert_eq!(limits.movetime, Some(Duration::from_millis(950)));
+        let limits = parse_go(&["movetime", "100", "depth", "4"], Color::White);
+        assert_eq!(limits.movetime, Some(Duration::from_millis(100)));
+        assert_eq!(limits.depth, Some(4));
+        assert!(!limits.infinite);
+        assert_eq!(parse_go(&["nodes", "1000"], Color::White).nodes, Some(1000));
+        assert!(parse_go(&[], Color::White).infinite);
+
+        //Words without a number don't swallow the keyword after them
+        let limits = parse_go(
+            &[
+                "searchmoves",
+                "e2e4",
+                "d2d4",
+                "depth",
+                "5",
+                "ponder",
+                "nodes",
+                "10",
+            ],
+            Color::White,
+        );
+        assert_eq!((limits.depth, limits.nodes), (Some(5), Some(10)));
+        assert!(!limits.infinite);
+    }
+}
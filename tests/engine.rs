@@ -0,0 +1,33 @@
+use chess::{evaluate, mate_in, repl, Board, SearchLimits};
+
+#[test]
+fn finds_mate_in_one() {
+    let board: Board = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1".parse().unwrap();
+    let limits = SearchLimits {
+        depth: Some(3),
+        ..SearchLimits::default()
+    };
+    let info = board.search(&limits).unwrap();
+    assert_eq!(info.best_move().to_string(), "a1a8");
+    assert_eq!(mate_in(info.score), Some(1));
+}
+
+#[test]
+fn start_position_evaluates_to_zero() {
+    let board = Board::construct_board();
+    assert_eq!(evaluate(&board), 0);
+    assert_eq!(board.evaluation().total(), 0);
+}
+
+#[test]
+fn repl_plays_moves() {
+    let mut output = Vec::new();
+    repl::run(
+        Board::construct_board(),
+        "e4\nfen\nquit\n".as_bytes(),
+        &mut output,
+    )
+    .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"));
+}
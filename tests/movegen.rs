@@ -0,0 +1,94 @@
+use chess::{
+    Board, BoardBuilder, Color, GameOutcome, IllegalMove, Move, Piece, PieceType, PositionError,
+    PositionIssue, Square,
+};
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+#[test]
+fn perft_counts() {
+    let board = Board::construct_board();
+    assert_eq!(board.legal_moves().len(), 20);
+    assert_eq!(board.perft(3), 8_902);
+
+    let board: Board = KIWIPETE.parse().unwrap();
+    assert_eq!(board.perft(2), 2_039);
+    let divide = board.divide(1);
+    assert_eq!(divide.len(), 48);
+    assert!(divide.iter().all(|(_, nodes)| *nodes == 1));
+}
+
+#[test]
+fn making_and_unmaking_moves() {
+    let mut board = Board::construct_board();
+    let e4: Move = "e2e4".parse().unwrap();
+    let undo = board.make_move(e4).unwrap();
+    assert_eq!(undo.played_move(), e4);
+    assert_eq!(board.current_turn(), Color::Black);
+    assert_eq!(
+        board.get_piece(Square::E4),
+        Some(Piece::new(Color::White, PieceType::Pawn))
+    );
+    assert_eq!(board.en_passant_target(), "e3".parse().ok());
+
+    assert_eq!(
+        board.make_move("e4e5".parse().unwrap()),
+        Err(IllegalMove::WrongColor(Square::E4))
+    );
+    assert_eq!(
+        board.make_move("e7e4".parse().unwrap()),
+        Err(IllegalMove::NotLegal("e7e4".parse().unwrap()))
+    );
+
+    board.unmake_move(undo);
+    assert_eq!(board, Board::construct_board());
+}
+
+#[test]
+fn attacks_and_check() {
+    let board: Board = "4k3/8/8/8/8/8/3q4/4K3 w - - 0 1".parse().unwrap();
+    assert!(board.is_in_check(Color::White));
+    let king = Square::E1;
+    assert_eq!(board.attackers_of(king, Color::Black), vec![Square::D2]);
+    //The king has to take the queen or step to the one square it doesn't cover
+    let mut moves: Vec<String> = board.legal_moves().iter().map(|m| m.to_string()).collect();
+    moves.sort();
+    assert_eq!(moves, ["e1d2", "e1f1"]);
+    assert_ne!(board.attack_map(Color::Black), 0);
+}
+
+#[test]
+fn game_outcomes() {
+    let mut board = Board::construct_board();
+    for m in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+        board.make_move(m.parse().unwrap()).unwrap();
+    }
+    assert_eq!(
+        board.outcome(),
+        Some(GameOutcome::Checkmate {
+            winner: Color::Black
+        })
+    );
+    assert!(board.legal_moves().is_empty());
+}
+
+#[test]
+fn building_positions() {
+    let board = BoardBuilder::new()
+        .place(Square::G1, Piece::new(Color::White, PieceType::King))
+        .place(Square::A7, Piece::new(Color::White, PieceType::Pawn))
+        .place(Square::H8, Piece::new(Color::Black, PieceType::King))
+        .build()
+        .unwrap();
+    assert_eq!(board.to_fen_string(), "7k/P7/8/8/8/8/8/6K1 w - - 0 1");
+    assert_eq!(board.perft(1), 9);
+
+    let mut builder = BoardBuilder::from_board(&board);
+    builder.place(Square::A8, Piece::new(Color::White, PieceType::Pawn));
+    assert_eq!(
+        builder.build(),
+        Err(PositionError {
+            issues: vec![PositionIssue::PawnOnBackRank(Square::A8)]
+        })
+    );
+}
@@ -0,0 +1,48 @@
+use chess::{Board, FenError, Game, Move, PgnGame, SanError, Square};
+
+#[test]
+fn fen_round_trips() {
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/8/8/3pP3/8/8/8/R3K2R w Kq d6 7 31",
+    ] {
+        let board: Board = fen.parse().unwrap();
+        assert_eq!(board.to_fen_string(), fen);
+    }
+    assert_eq!("".parse::<Board>(), Err(FenError::Empty));
+}
+
+#[test]
+fn san_and_uci_moves() {
+    let board = Board::construct_board();
+    let m = board.parse_san("Nf3").unwrap();
+    assert_eq!(m.to_string(), "g1f3");
+    assert_eq!(m.to_san(&board), Ok("Nf3".to_string()));
+    assert_eq!(
+        Move::new(Square::E4, Square::E5).to_san(&board),
+        Err(SanError::IllegalMove("e4e5".to_string()))
+    );
+    assert_eq!("g1f3".parse(), Ok(m));
+    assert!(matches!(
+        board.parse_san("Ke2"),
+        Err(SanError::IllegalMove(_))
+    ));
+}
+
+#[test]
+fn games_and_pgn() {
+    let mut game = Game::new();
+    for san in ["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7#"] {
+        let m = game.board().parse_san(san).unwrap();
+        game.play(m).unwrap();
+    }
+    assert_eq!(game.result(), "1-0");
+
+    let pgn = game.to_pgn();
+    assert!(pgn.contains("1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0"));
+    let parsed: PgnGame = pgn.parse().unwrap();
+    assert_eq!(parsed.moves.len(), 7);
+    assert_eq!(parsed.final_board(), *game.board());
+    assert_eq!(Game::from(parsed).to_pgn(), pgn);
+}